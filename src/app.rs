@@ -2,6 +2,8 @@ use crate::*;
 
 use std::io::Stdout;
 
+use clap::ArgMatches;
+
 #[derive()]
 pub struct App {
     pub ui: Ui<Stdout>,
@@ -12,29 +14,18 @@ pub struct App {
 }
 
 impl App {
-    pub fn init() -> anyhow::Result<Self> {
-        let cli = cli::new();
-        let args = cli.clone().get_matches();
-
-        let md_file = match args.get_one::<String>("file") {
-            Some(path) => path.into(),
-            _ => {
-                Log::warn("No filename specified -- falling back to `todo.md`");
-                "todo.md".to_string()
-            }
-        };
+    pub fn init(args: &ArgMatches) -> anyhow::Result<Self> {
+        let md_file = cli::md_file(args);
+        let document = cli::read_document(&md_file, args)?;
 
-        if !path::Path::new(&md_file).exists() {
-            Log::error_exit_with(
-                ErrorCode::IO,
-                format!("Markdown file `{md_file}` not found. Exiting..."),
-            );
+        let mut ui = Ui::init(io::stdout(), document);
+        if let Some(&hours) = args.get_one::<u32>("due-soon") {
+            ui.due_soon = chrono::Duration::hours(hours.into());
         }
-
-        let md = fs::read_to_string(&md_file).unwrap();
-        let document = Document::from_md(md)?;
-
-        let ui = Ui::init(io::stdout(), document);
+        if let Some(cycle) = args.get_one::<Vec<Status>>("status-cycle") {
+            ui.status_cycle = cycle.clone();
+        }
+        ui.archive_recurring = args.get_flag("archive-recurring");
 
         Ok(Self {
             ui,
@@ -47,16 +38,51 @@ impl App {
 
     pub fn handle_input(&mut self) -> anyhow::Result<()> {
         if poll(time::Duration::from_millis(250)).unwrap_or(false) {
-            if let Ok(Key(k)) = read() {
+            if let Ok(Key(mut k)) = read() {
+                // the case of the char already tells whether shift was held
+                if let Char(_) | KeyCode::BackTab = k.code {
+                    k.modifiers.remove(KeyModifiers::SHIFT);
+                }
+                self.ui.message = None;
                 let a = self.keymap.map.get(&(self.mode.clone(), k));
                 if let Some(callback) = a {
                     (callback.clone())(self);
+                } else if let Mode::Insert(_) = self.mode {
+                    self.ui.editor.handle_key(k);
+                } else if let (Mode::Datetime, Char(c)) = (&self.mode, k.code) {
+                    self.ui.datetime_editor.input_digit(c);
+                } else if let (Mode::Prompt, Char(c)) = (&self.mode, k.code) {
+                    self.ui.prompt_input(c);
                 }
+                self.sync_mode();
             }
         }
         Ok(())
     }
 
+    /// keeps `self.mode` in line with the state of the prompt, line and datetime
+    /// editors, the tag picker and the notes pane
+    pub fn sync_mode(&mut self) {
+        if self.ui.is_prompting() {
+            self.mode = Mode::Prompt;
+        } else if self.ui.is_editing() {
+            self.mode = Mode::Insert(self.ui.editor.mode.clone());
+        } else if self.ui.is_editing_deadline() {
+            self.mode = Mode::Datetime;
+        } else if self.ui.is_picking_tags() {
+            self.mode = Mode::TagPicker;
+        } else if self.ui.is_viewing_notes() {
+            self.mode = Mode::Notes;
+        } else if let Mode::Insert(_)
+        | Mode::Datetime
+        | Mode::Prompt
+        | Mode::TagPicker
+        | Mode::Notes = self.mode
+        {
+            self.mode = Mode::Normal;
+        }
+    }
+
     pub fn quit(&mut self) {
         self.running = false;
     }
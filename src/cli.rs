@@ -1,4 +1,6 @@
-use clap::{command, value_parser, Arg, ArgAction, Command, ValueHint};
+use crate::*;
+
+use clap::{command, value_parser, Arg, ArgAction, ArgGroup, ArgMatches, Command, ValueHint};
 
 pub fn new() -> Command {
     command!()
@@ -11,4 +13,272 @@ pub fn new() -> Command {
                 .value_parser(value_parser!(String))
                 .value_hint(ValueHint::FilePath),
         )
+        .arg(
+            Arg::new("due-soon")
+                .help("Highlight deadlines within this many hours as due soon")
+                .long("due-soon")
+                .action(ArgAction::Set)
+                .value_name("HOURS")
+                .value_parser(value_parser!(u32))
+                .default_value("24")
+                .global(true),
+        )
+        .arg(
+            Arg::new("dialect")
+                .help("Rewrite all deadlines as `(2024-06-20 16:00)` (todue) or `📅 2024-06-20` (obsidian) when saving")
+                .long("dialect")
+                .action(ArgAction::Set)
+                .value_name("DIALECT")
+                .value_parser(["todue", "obsidian"])
+                .global(true),
+        )
+        .arg(
+            Arg::new("status-cycle")
+                .help("Checkbox states <space> cycles through in the tui, e.g. ` /x` for open, in progress and done")
+                .long("status-cycle")
+                .action(ArgAction::Set)
+                .value_name("STATES")
+                .value_parser(parse_status_cycle)
+                .default_value(" x")
+                .global(true),
+        )
+        .arg(
+            Arg::new("archive-recurring")
+                .help("Move completed recurring entries to an `Archive` section instead of keeping them in place")
+                .long("archive-recurring")
+                .action(ArgAction::SetTrue)
+                .global(true),
+        )
+        .subcommand(
+            Command::new("add")
+                .about("Append an entry to FILE without starting the tui")
+                .arg(
+                    Arg::new("text")
+                        .help("Text of the entry")
+                        .required(true)
+                        .value_name("TEXT")
+                        .value_parser(value_parser!(String)),
+                )
+                .arg(
+                    Arg::new("due")
+                        .help("Deadline, e.g. `2024-07-01 12:00`, `tomorrow 9am` or `in 3d`")
+                        .long("due")
+                        .short('d')
+                        .value_name("DEADLINE")
+                        .value_parser(value_parser!(String)),
+                )
+                .arg(
+                    Arg::new("section")
+                        .help("Heading of the section to add the entry to (default: the last one other than `Archive`)")
+                        .long("section")
+                        .short('s')
+                        .value_name("NAME")
+                        .value_parser(value_parser!(String)),
+                ),
+        )
+        .subcommand(
+            Command::new("list")
+                .about("Print the entries of FILE, optionally filtered")
+                .arg(
+                    Arg::new("open")
+                        .help("Only list entries that are not completed")
+                        .long("open")
+                        .action(ArgAction::SetTrue),
+                )
+                .arg(
+                    Arg::new("due-before")
+                        .help("Only list entries due before DEADLINE (dates without a time mean the start of that day)")
+                        .long("due-before")
+                        .value_name("DEADLINE")
+                        .value_parser(value_parser!(String)),
+                )
+                .arg(
+                    Arg::new("completed-since")
+                        .help("Only list entries completed since DATE, e.g. `2024-06-17` or `2024-06-17 09:00`")
+                        .long("completed-since")
+                        .value_name("DATE")
+                        .value_parser(value_parser!(String)),
+                )
+                .arg(
+                    Arg::new("section")
+                        .help("Only list entries in the section with this heading")
+                        .long("section")
+                        .short('s')
+                        .value_name("NAME")
+                        .value_parser(value_parser!(String)),
+                )
+                .arg(
+                    Arg::new("format")
+                        .help("Output format")
+                        .long("format")
+                        .short('f')
+                        .value_name("FORMAT")
+                        .value_parser(["plain", "md", "json"])
+                        .default_value("plain"),
+                ),
+        )
+        .subcommand(entry_command("done", "Mark an entry of FILE as completed"))
+        .subcommand(entry_command("undone", "Mark an entry of FILE as not completed"))
+        .subcommand(entry_command("rm", "Remove an entry of FILE including its subtasks"))
+        .subcommand(
+            format_command("export", "Convert FILE to another format")
+                .arg(
+                    Arg::new("output")
+                        .help("File to write to (default: stdout)")
+                        .long("output")
+                        .short('o')
+                        .value_name("OUTPUT")
+                        .value_parser(value_parser!(String))
+                        .value_hint(ValueHint::FilePath),
+                ),
+        )
+        .subcommand(
+            format_command("import", "Create FILE from another format")
+                .arg(
+                    Arg::new("input")
+                        .help("File to read from (default: stdin)")
+                        .value_name("INPUT")
+                        .value_parser(value_parser!(String))
+                        .value_hint(ValueHint::FilePath),
+                )
+                .arg(
+                    Arg::new("force")
+                        .help("Overwrite FILE if it already exists")
+                        .long("force")
+                        .action(ArgAction::SetTrue),
+                ),
+        )
+}
+
+/// a subcommand converting from/to one of the formats given as flags
+fn format_command(name: &'static str, about: &'static str) -> Command {
+    Command::new(name)
+        .about(about)
+        .arg(
+            Arg::new("json")
+                .help("Json mirroring todue's document structure")
+                .long("json")
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("ics")
+                .help("iCalendar with a VTODO for each entry with a deadline")
+                .long("ics")
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("todo-txt")
+                .help("todo.txt, one line per entry")
+                .long("todo-txt")
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("md")
+                .help("Markdown, e.g. to convert a todo.txt FILE")
+                .long("md")
+                .action(ArgAction::SetTrue),
+        )
+        .group(
+            ArgGroup::new("format")
+                .args(["json", "ics", "todo-txt", "md"])
+                .required(true),
+        )
+}
+
+/// a subcommand working on a single entry
+fn entry_command(name: &'static str, about: &'static str) -> Command {
+    Command::new(name)
+        .about(about)
+        .arg(
+            Arg::new("entry")
+                .help("Index (as shown by `list`, optionally as `#3`), exact text or part of the text of the entry")
+                .required(true)
+                .value_name("ENTRY")
+                .value_parser(value_parser!(String)),
+        )
+        .arg(
+            Arg::new("regex")
+                .help("Match ENTRY as a regular expression against the entries' text")
+                .long("regex")
+                .short('r')
+                .action(ArgAction::SetTrue),
+        )
+}
+
+/// statuses from their checkbox characters, as given to `--status-cycle`
+fn parse_status_cycle(value: &str) -> Result<Vec<Status>, String> {
+    if value.is_empty() {
+        return Err("expected at least one of ' ', 'x', '-', '/' and '>'".into());
+    }
+    value
+        .chars()
+        .map(|c| Status::from_char(c).ok_or(format!("'{c}' is not a checkbox state")))
+        .collect()
+}
+
+/// the markdown file given on the command line, `todo.md` by default
+pub fn md_file(args: &ArgMatches) -> String {
+    match args.get_one::<String>("file") {
+        Some(path) => path.into(),
+        _ => {
+            Log::warn("No filename specified -- falling back to `todo.md`");
+            "todo.md".to_string()
+        }
+    }
+}
+
+/// reads and parses `md_file`, exiting if it doesn't exist. entries are
+/// converted to the `--dialect` given in `args`, if any
+pub fn read_document(md_file: &str, args: &ArgMatches) -> anyhow::Result<Document> {
+    if !path::Path::new(md_file).exists() {
+        Log::error_exit_with(
+            ErrorCode::IO,
+            format!("File `{md_file}` not found. Exiting..."),
+        );
+    }
+    let contents = fs::read_to_string(md_file)?;
+    let mut document = FileFormat::of(md_file).parse(contents)?;
+    if let Some(dialect) = dialect(args) {
+        document.set_dialect(dialect);
+    }
+    Ok(document)
+}
+
+/// the dialect given with `--dialect`
+pub fn dialect(args: &ArgMatches) -> Option<Dialect> {
+    match args.get_one::<String>("dialect")?.as_str() {
+        "obsidian" => Some(Dialect::Obsidian),
+        _ => Some(Dialect::Todue),
+    }
+}
+
+/// formats of the files todue works on
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FileFormat {
+    Markdown,
+    TodoTxt,
+}
+
+impl FileFormat {
+    /// todo.txt for `.txt` files, markdown for everything else
+    pub fn of(path: &str) -> Self {
+        match path::Path::new(path).extension() {
+            Some(ext) if ext.eq_ignore_ascii_case("txt") => FileFormat::TodoTxt,
+            _ => FileFormat::Markdown,
+        }
+    }
+
+    pub fn parse(self, contents: String) -> anyhow::Result<Document> {
+        match self {
+            FileFormat::Markdown => Document::from_md(contents),
+            FileFormat::TodoTxt => Document::from_todo_txt(contents),
+        }
+    }
+
+    pub fn serialize(self, document: &Document) -> String {
+        match self {
+            FileFormat::Markdown => document.to_md(),
+            FileFormat::TodoTxt => document.to_todo_txt(),
+        }
+    }
 }
@@ -0,0 +1,432 @@
+use crate::*;
+
+use std::io::IsTerminal;
+
+use anyhow::{bail, Context};
+use chrono::{Duration, NaiveDate, NaiveDateTime, NaiveTime};
+use clap::ArgMatches;
+use serde::Serialize;
+use termcolor::{Color, ColorChoice, ColorSpec, StandardStream, WriteColor};
+
+/// runs the subcommand `command` on the document in `md_file`
+pub fn run(command: &str, md_file: &str, args: &ArgMatches) -> anyhow::Result<()> {
+    match command {
+        "add" => add(md_file, args),
+        "list" => list(md_file, args),
+        "done" | "undone" | "rm" => edit_entry(command, md_file, args),
+        "export" => export(md_file, args),
+        "import" => import(md_file, args),
+        _ => bail!("Unknown command `{command}`"),
+    }
+}
+
+/// `todue add TEXT [--due DEADLINE] [--section NAME]`
+fn add(md_file: &str, args: &ArgMatches) -> anyhow::Result<()> {
+    let mut document = cli::read_document(md_file, args)?;
+    let text = args.get_one::<String>("text").unwrap();
+    let now = chrono::Local::now().naive_local();
+    let deadline = match args.get_one::<String>("due") {
+        Some(due) => Some(parse_deadline(due, now)?),
+        None => None,
+    };
+    let section = args.get_one::<String>("section").map(String::as_str);
+
+    let path = add_entry(&mut document, text, deadline, section)?;
+    document.entry_mut(&path).unwrap().created = Some(stamp(now));
+    write_document(md_file, &document)?;
+    Log::info(format!("Added `{}` to `{md_file}`", text.trim()));
+    Ok(())
+}
+
+/// appends a new entry to the section named `section`, or the last section
+pub fn add_entry(
+    document: &mut Document,
+    text: &str,
+    deadline: Option<NaiveDateTime>,
+    section: Option<&str>,
+) -> anyhow::Result<EntryPath> {
+    let text = text.trim();
+    if text.is_empty() || text.contains('\n') {
+        bail!("Entry text must be a single, non-empty line");
+    }
+    let section = match section {
+        Some(name) => document
+            .section_idx(name)
+            .with_context(|| format!("No section named `{name}`"))?,
+        None => document.default_section(),
+    };
+    let mut entry = Entry {
+        text: text.to_string(),
+        deadline,
+        ..Default::default()
+    };
+    entry.parse_tags();
+    Ok(document.push_entry(section, entry).unwrap())
+}
+
+/// which entries `todue list` prints
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct ListOptions {
+    pub open: bool,
+    pub due_before: Option<NaiveDateTime>,
+    pub completed_since: Option<NaiveDateTime>,
+    pub section: Option<String>,
+}
+
+/// how `todue list` prints entries
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum ListFormat {
+    #[default]
+    Plain,
+    Md,
+    Json,
+}
+
+/// an entry as printed by `todue list --format json`
+#[derive(Debug, Serialize)]
+struct ListedEntry<'a> {
+    index: usize,
+    section: Option<&'a str>,
+    depth: usize,
+    status: Status,
+    /// ISO 8601, e.g. `2024-06-20T16:00:00`
+    deadline: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    created: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    completed: Option<String>,
+    text: &'a str,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    notes: Vec<String>,
+}
+
+/// `todue list [--open] [--due-before DEADLINE] [--completed-since DATE] [--section NAME] [--format FORMAT]`
+fn list(md_file: &str, args: &ArgMatches) -> anyhow::Result<()> {
+    let document = cli::read_document(md_file, args)?;
+    let now = chrono::Local::now().naive_local();
+    // `--due-before 2024-07-01` shouldn't include entries due on july 1st
+    let due_before = match args.get_one::<String>("due-before") {
+        Some(due) => Some(parse_datetime(due, now, NaiveTime::MIN)?),
+        None => None,
+    };
+    let completed_since = match args.get_one::<String>("completed-since") {
+        Some(since) => Some(parse_since(since)?),
+        None => None,
+    };
+    let options = ListOptions {
+        open: args.get_flag("open"),
+        due_before,
+        completed_since,
+        section: args.get_one::<String>("section").cloned(),
+    };
+    let format = match args.get_one::<String>("format").map(String::as_str) {
+        Some("md") => ListFormat::Md,
+        Some("json") => ListFormat::Json,
+        _ => ListFormat::Plain,
+    };
+    let due_soon = Duration::hours((*args.get_one::<u32>("due-soon").unwrap_or(&24)).into());
+
+    if options
+        .section
+        .as_ref()
+        .is_some_and(|name| document.section_idx(name).is_none())
+    {
+        bail!("No section named `{}`", options.section.unwrap());
+    }
+
+    // colors only make sense for people looking at a terminal
+    let no_color = std::env::var_os("NO_COLOR").is_some_and(|v| !v.is_empty());
+    let color = match no_color || !io::stdout().is_terminal() || format == ListFormat::Json {
+        true => ColorChoice::Never,
+        false => ColorChoice::Auto,
+    };
+    let mut out = StandardStream::stdout(color);
+    let listed = list_entries(&document, &options);
+    match write_list(&mut out, &document, &listed, format, now, due_soon) {
+        // e.g. piped into `head`, which doesn't need the rest
+        Err(e)
+            if e.downcast_ref::<io::Error>()
+                .is_some_and(|e| e.kind() == io::ErrorKind::BrokenPipe) =>
+        {
+            Ok(())
+        }
+        result => result,
+    }
+}
+
+/// a date (meaning its start) or date and time to list completed entries since
+fn parse_since(since: &str) -> anyhow::Result<NaiveDateTime> {
+    let since = since.trim();
+    NaiveDateTime::parse_from_str(since, "%Y-%m-%d %H:%M")
+        .or_else(|_| {
+            NaiveDate::parse_from_str(since, "%Y-%m-%d").map(|d| d.and_time(NaiveTime::MIN))
+        })
+        .with_context(|| format!("Invalid date `{since}`, expected e.g. `2024-06-17`"))
+}
+
+/// 1-based indices (as used by the other commands) and paths of the entries
+/// matching `options`, in document order
+pub fn list_entries(document: &Document, options: &ListOptions) -> Vec<(usize, EntryPath)> {
+    let section = options
+        .section
+        .as_ref()
+        .and_then(|name| document.section_idx(name));
+    document
+        .entry_paths(false)
+        .into_iter()
+        .enumerate()
+        .filter(|(_, path)| {
+            let entry = document.entry(path).unwrap();
+            (!options.open || !entry.status.is_closed())
+                && options
+                    .due_before
+                    .is_none_or(|before| entry.deadline.is_some_and(|d| d < before))
+                && options
+                    .completed_since
+                    .is_none_or(|since| entry.completed.is_some_and(|c| c >= since))
+                && section.is_none_or(|section| path[0] == section)
+        })
+        .map(|(i, path)| (i + 1, path))
+        .collect()
+}
+
+fn iso(datetime: Option<NaiveDateTime>) -> Option<String> {
+    datetime.map(|d| d.format("%Y-%m-%dT%H:%M:%S").to_string())
+}
+
+/// prints the `listed` entries in `format`, colored by their deadline
+pub fn write_list(
+    out: &mut impl WriteColor,
+    document: &Document,
+    listed: &[(usize, EntryPath)],
+    format: ListFormat,
+    now: NaiveDateTime,
+    due_soon: Duration,
+) -> anyhow::Result<()> {
+    if format == ListFormat::Json {
+        let entries: Vec<_> = listed
+            .iter()
+            .map(|(index, path)| {
+                let entry = document.entry(path).unwrap();
+                let section = &document.sections[path[0]];
+                ListedEntry {
+                    index: *index,
+                    section: section.heading.as_ref().map(|_| section.name.as_str()),
+                    depth: path.len() - 2,
+                    status: entry.status,
+                    deadline: iso(entry.deadline),
+                    created: iso(entry.created),
+                    completed: iso(entry.completed),
+                    text: &entry.text,
+                    notes: entry.notes(),
+                }
+            })
+            .collect();
+        writeln!(out, "{}", serde_json::to_string_pretty(&entries)?)?;
+        return Ok(());
+    }
+
+    for (index, path) in listed {
+        let entry = document.entry(path).unwrap();
+        let mut spec = ColorSpec::new();
+        match Urgency::of(entry, now, due_soon) {
+            _ if entry.status.is_closed() => spec.set_dimmed(true),
+            Some(Urgency::Overdue) => spec.set_fg(Some(Color::Red)).set_bold(true),
+            Some(Urgency::DueToday) => spec.set_fg(Some(Color::Magenta)),
+            Some(Urgency::DueSoon) => spec.set_fg(Some(Color::Cyan)),
+            None => &mut spec,
+        };
+        let indent = "  ".repeat(path.len() - 2);
+        let line = match format {
+            ListFormat::Md => indent + &entry.to_md(),
+            _ => {
+                let deadline = match entry.deadline {
+                    Some(deadline) => deadline.format("%Y-%m-%d %H:%M").to_string(),
+                    None => String::new(),
+                };
+                let checkbox = format!("[{}]", entry.status.to_char());
+                let notes = if entry.has_notes() { " ≡" } else { "" };
+                format!(
+                    "{index:>3} {checkbox} {deadline:16}  {indent}{}{notes}",
+                    entry.text
+                )
+            }
+        };
+        out.set_color(&spec)?;
+        write!(out, "{}", line)?;
+        out.reset()?;
+        writeln!(out)?;
+    }
+    Ok(())
+}
+
+/// `todue done|undone|rm ENTRY [--regex]`
+fn edit_entry(command: &str, md_file: &str, args: &ArgMatches) -> anyhow::Result<()> {
+    let mut document = cli::read_document(md_file, args)?;
+    let query = args.get_one::<String>("entry").unwrap();
+    let path = find_entry(&document, query, args.get_flag("regex"))?;
+    let text = document.entry(&path).unwrap().text.clone();
+    match command {
+        "rm" => {
+            document.remove_entry(&path);
+            Log::info(format!("Removed `{text}`"));
+        }
+        _ => {
+            let done = command == "done";
+            let entry = document.entry_mut(&path).unwrap();
+            let completes = done && entry.status != Status::Done;
+            let now = chrono::Local::now().naive_local();
+            match done {
+                true => entry.set_status(Status::Done, now),
+                false => entry.set_status(Status::Open, now),
+            }
+            let status = if done { "completed" } else { "not completed" };
+            Log::info(format!("Marked `{text}` as {status}"));
+            let archive = args.get_flag("archive-recurring");
+            if let Some(next) = completes
+                .then(|| document.respawn_recurring(&path, archive, now))
+                .flatten()
+            {
+                let deadline = document.entry(&next).unwrap().deadline.unwrap();
+                Log::info(format!(
+                    "Next occurrence due {}",
+                    deadline.format("%Y-%m-%d %H:%M")
+                ));
+            }
+        }
+    }
+    write_document(md_file, &document)
+}
+
+/// path of the single entry `query` refers to: a 1-based index (optionally
+/// written as `#3`), the exact text of an entry, or (ignoring case) a part of it.
+/// with `regex`, `query` is matched as a regular expression instead. errs unless
+/// exactly one matches, or if a number is both an index and an entry's text
+pub fn find_entry(document: &Document, query: &str, regex: bool) -> anyhow::Result<EntryPath> {
+    let paths = document.entry_paths(false);
+    let text = |path: &EntryPath| document.entry(path).unwrap().text.as_str();
+    let by_index = |index: usize| index.checked_sub(1).and_then(|i| paths.get(i).cloned());
+
+    if !regex {
+        let query = query.trim();
+        if let Some(Ok(index)) = query.strip_prefix('#').map(str::parse::<usize>) {
+            return by_index(index).with_context(|| format!("No entry with index {index}"));
+        }
+        if let Ok(index) = query.parse::<usize>() {
+            let named = paths.iter().any(|path| text(path) == query);
+            match (by_index(index), named) {
+                (Some(path), false) => return Ok(path),
+                (None, false) => bail!("No entry with index {index}"),
+                (Some(_), true) => bail!(
+                    "`{query}` is both an index and the text of an entry, \
+                    use `#{query}` for the index or `--regex '^{query}$'` for the text"
+                ),
+                // looked up by its text below
+                (None, true) => {}
+            }
+        }
+    }
+
+    let matches: Vec<(usize, &EntryPath)> = match regex {
+        true => {
+            let regex = regex::Regex::new(query)?;
+            paths
+                .iter()
+                .enumerate()
+                .filter(|(_, path)| regex.is_match(text(path)))
+                .collect()
+        }
+        false => {
+            let exact: Vec<_> = paths
+                .iter()
+                .enumerate()
+                .filter(|(_, path)| text(path) == query.trim())
+                .collect();
+            match exact.is_empty() {
+                true => {
+                    let query = query.trim().to_lowercase();
+                    paths
+                        .iter()
+                        .enumerate()
+                        .filter(|(_, path)| text(path).to_lowercase().contains(&query))
+                        .collect()
+                }
+                false => exact,
+            }
+        }
+    };
+
+    match matches.as_slice() {
+        [] => bail!("No entry matches `{query}`"),
+        [(_, path)] => Ok(path.to_vec()),
+        _ => {
+            let candidates: Vec<String> = matches
+                .iter()
+                .map(|(i, path)| format!("  {}: {}", i + 1, text(path)))
+                .collect();
+            bail!(
+                "`{query}` matches {} entries, use an index instead:\n{}",
+                matches.len(),
+                candidates.join("\n")
+            )
+        }
+    }
+}
+
+/// `todue export --json|--ics|--todo-txt|--md [--output OUTPUT]`
+fn export(md_file: &str, args: &ArgMatches) -> anyhow::Result<()> {
+    let document = cli::read_document(md_file, args)?;
+    let exported = match format_flag(args) {
+        "ics" => ical::to_ics(&document, chrono::Utc::now().naive_utc()),
+        "todo-txt" => document.to_todo_txt(),
+        "md" => document.to_md(),
+        _ => json::to_json(&document)? + "\n",
+    };
+    match args.get_one::<String>("output") {
+        Some(output) => fs::write(output, exported)
+            .with_context(|| format!("Failed to write to file: `{output}`")),
+        None => {
+            print!("{exported}");
+            Ok(())
+        }
+    }
+}
+
+/// `todue import --json|--ics|--todo-txt|--md [INPUT] [--force]`
+fn import(md_file: &str, args: &ArgMatches) -> anyhow::Result<()> {
+    if path::Path::new(md_file).exists() && !args.get_flag("force") {
+        bail!("`{md_file}` already exists, use `--force` to overwrite it");
+    }
+    let input = match args.get_one::<String>("input") {
+        Some(input) => {
+            fs::read_to_string(input).with_context(|| format!("Failed to read file: `{input}`"))?
+        }
+        None => io::read_to_string(io::stdin())?,
+    };
+    let mut document = match format_flag(args) {
+        "ics" => ical::from_ics(&input)?,
+        "todo-txt" => Document::from_todo_txt(input)?,
+        "md" => Document::from_md(input)?,
+        _ => json::from_json(&input)?,
+    };
+    if let Some(dialect) = cli::dialect(args) {
+        document.set_dialect(dialect);
+    }
+    write_document(md_file, &document)?;
+    Log::info(format!("Imported `{md_file}`"));
+    Ok(())
+}
+
+/// name of the format flag given to `export`/`import`
+fn format_flag(args: &ArgMatches) -> &'static str {
+    ["json", "ics", "todo-txt", "md"]
+        .into_iter()
+        .find(|&flag| args.get_flag(flag))
+        .unwrap_or("json")
+}
+
+/// writes `document` back to `md_file`, in the format matching its extension
+pub fn write_document(md_file: &str, document: &Document) -> anyhow::Result<()> {
+    fs::write(md_file, FileFormat::of(md_file).serialize(document))
+        .with_context(|| format!("Failed to write to file: `{md_file}`"))
+}
@@ -4,7 +4,7 @@ use crate::*;
 use std::collections::HashMap;
 
 use anyhow::*;
-use crossterm::event::KeyEvent;
+use crossterm::event::{KeyCode, KeyEvent};
 
 /// Mode of the TUI
 #[derive(Default, Debug, PartialEq, Eq, Hash, Clone)]
@@ -14,6 +14,9 @@ pub enum Mode {
     Insert(EditMode),
     Datetime,
     Visual,
+    Prompt,
+    TagPicker,
+    Notes,
 }
 
 /// Mode for the line editor
@@ -96,11 +99,335 @@ impl Default for Keymap {
             Char(' ').into(),
             Box::new(|app: &mut App| app.ui.toggle_active_entry()),
         );
+        map.register(
+            Normal,
+            Char('p').into(),
+            Box::new(|app: &mut App| app.ui.change_active_priority(true)),
+        );
+        map.register(
+            Normal,
+            Char('P').into(),
+            Box::new(|app: &mut App| app.ui.change_active_priority(false)),
+        );
+        map.register(
+            Normal,
+            Char('z').into(),
+            Box::new(|app: &mut App| app.ui.toggle_collapse_active_group()),
+        );
         map.register(
             Normal,
             Char('s').into(),
             Box::new(|app: &mut App| app.ui.cycle_sort_mode()),
         );
+        map.register(
+            Normal,
+            Char('t').into(),
+            Box::new(|app: &mut App| app.ui.cycle_deadline_display()),
+        );
+        map.register(
+            Normal,
+            Char('f').into(),
+            Box::new(|app: &mut App| app.ui.cycle_filter()),
+        );
+        map.register(
+            Normal,
+            Char('F').into(),
+            Box::new(|app: &mut App| app.ui.prompt_filter()),
+        );
+        map.register(
+            Normal,
+            Char('#').into(),
+            Box::new(|app: &mut App| app.ui.open_tag_picker()),
+        );
+        for key in [KeyCode::Down, Char('j')] {
+            map.register(
+                TagPicker,
+                key.into(),
+                Box::new(|app: &mut App| app.ui.move_tag_cursor(Down)),
+            );
+        }
+        for key in [KeyCode::Up, Char('k')] {
+            map.register(
+                TagPicker,
+                key.into(),
+                Box::new(|app: &mut App| app.ui.move_tag_cursor(Up)),
+            );
+        }
+        map.register(
+            TagPicker,
+            Char(' ').into(),
+            Box::new(|app: &mut App| app.ui.toggle_picked_tag()),
+        );
+        map.register(
+            TagPicker,
+            KeyCode::Enter.into(),
+            Box::new(|app: &mut App| app.ui.submit_tag_picker()),
+        );
+        for key in [KeyCode::Esc, Char('q')] {
+            map.register(
+                TagPicker,
+                key.into(),
+                Box::new(|app: &mut App| app.ui.cancel_tag_picker()),
+            );
+        }
+        map.register(
+            Normal,
+            Char('e').into(),
+            Box::new(|app: &mut App| app.ui.open_notes_pane()),
+        );
+        for key in [KeyCode::Down, Char('j')] {
+            map.register(
+                Notes,
+                key.into(),
+                Box::new(|app: &mut App| app.ui.move_notes_cursor(Down)),
+            );
+        }
+        for key in [KeyCode::Up, Char('k')] {
+            map.register(
+                Notes,
+                key.into(),
+                Box::new(|app: &mut App| app.ui.move_notes_cursor(Up)),
+            );
+        }
+        map.register(
+            Notes,
+            KeyCode::Enter.into(),
+            Box::new(|app: &mut App| app.ui.edit_note()),
+        );
+        map.register(
+            Notes,
+            Char('o').into(),
+            Box::new(|app: &mut App| app.ui.open_new_note(Down)),
+        );
+        map.register(
+            Notes,
+            Char('O').into(),
+            Box::new(|app: &mut App| app.ui.open_new_note(Up)),
+        );
+        map.register(
+            Notes,
+            Char('d').into(),
+            Box::new(|app: &mut App| app.ui.delete_note()),
+        );
+        for key in [KeyCode::Esc, Char('q'), Char('e')] {
+            map.register(
+                Notes,
+                key.into(),
+                Box::new(|app: &mut App| app.ui.close_notes_pane()),
+            );
+        }
+        map.register(
+            Normal,
+            Char('a').into(),
+            Box::new(|app: &mut App| {
+                app.ui
+                    .edit_active_entry(usize::MAX, EditMode::Insert, false)
+            }),
+        );
+        map.register(
+            Normal,
+            Char('A').into(),
+            Box::new(|app: &mut App| {
+                app.ui
+                    .edit_active_entry(usize::MAX, EditMode::Insert, false)
+            }),
+        );
+        map.register(
+            Normal,
+            Char('i').into(),
+            Box::new(|app: &mut App| app.ui.edit_active_entry(0, EditMode::Insert, false)),
+        );
+        map.register(
+            Normal,
+            Char('I').into(),
+            Box::new(|app: &mut App| app.ui.edit_active_entry(0, EditMode::Insert, false)),
+        );
+        map.register(
+            Normal,
+            Char('c').into(),
+            Box::new(|app: &mut App| app.ui.edit_active_entry(0, EditMode::Insert, true)),
+        );
+        map.register(
+            Normal,
+            Char('C').into(),
+            Box::new(|app: &mut App| app.ui.edit_active_entry(0, EditMode::Insert, true)),
+        );
+        map.register(
+            Normal,
+            Char('r').into(),
+            Box::new(|app: &mut App| app.ui.edit_active_entry(0, EditMode::Replace, false)),
+        );
+        map.register(
+            Normal,
+            KeyCode::Enter.into(),
+            Box::new(|app: &mut App| app.ui.edit_active_entry(0, EditMode::Normal, false)),
+        );
+        map.register(
+            Normal,
+            Char('o').into(),
+            Box::new(|app: &mut App| app.ui.open_new_entry(Down)),
+        );
+        map.register(
+            Normal,
+            Char('O').into(),
+            Box::new(|app: &mut App| app.ui.open_new_entry(Up)),
+        );
+
+        // line editor; keys without mapping are handled by `LineEditor::handle_key`
+        for edit_mode in [
+            EditMode::Normal,
+            EditMode::Insert,
+            EditMode::Visual,
+            EditMode::Replace,
+        ] {
+            map.register(
+                Insert(edit_mode.clone()),
+                KeyCode::Enter.into(),
+                Box::new(|app: &mut App| app.ui.commit_edit()),
+            );
+            map.register(
+                Insert(edit_mode),
+                KeyEvent::new(Char('c'), KeyModifiers::CONTROL),
+                Box::new(|app: &mut App| app.ui.cancel_edit()),
+            );
+        }
+        map.register(
+            Insert(EditMode::Normal),
+            KeyCode::Esc.into(),
+            Box::new(|app: &mut App| app.ui.commit_edit()),
+        );
+
+        map.register(
+            Normal,
+            Char('d').into(),
+            Box::new(|app: &mut App| app.ui.edit_active_deadline()),
+        );
+
+        // datetime editor; digits are handled by `DatetimeEditor::input_digit`
+        map.register(
+            Datetime,
+            KeyCode::Enter.into(),
+            Box::new(|app: &mut App| app.ui.advance_datetime_editor()),
+        );
+        for key in [KeyCode::Esc, Char('q')] {
+            map.register(
+                Datetime,
+                key.into(),
+                Box::new(|app: &mut App| app.ui.cancel_deadline()),
+            );
+        }
+        map.register(
+            Datetime,
+            Char('i').into(),
+            Box::new(|app: &mut App| app.ui.prompt_deadline()),
+        );
+        map.register(
+            Datetime,
+            Char('d').into(),
+            Box::new(|app: &mut App| app.ui.remove_deadline()),
+        );
+        for key in [KeyCode::Tab, KeyCode::Right, Char('l')] {
+            map.register(
+                Datetime,
+                key.into(),
+                Box::new(|app: &mut App| {
+                    app.ui.datetime_editor.next_segment();
+                }),
+            );
+        }
+        for key in [KeyCode::BackTab, KeyCode::Left, Char('h')] {
+            map.register(
+                Datetime,
+                key.into(),
+                Box::new(|app: &mut App| app.ui.datetime_editor.prev_segment()),
+            );
+        }
+        for key in [KeyCode::Up, Char('k'), Char('+')] {
+            map.register(
+                Datetime,
+                key.into(),
+                Box::new(|app: &mut App| app.ui.datetime_editor.increment(1)),
+            );
+        }
+        for key in [KeyCode::Down, Char('j'), Char('-')] {
+            map.register(
+                Datetime,
+                key.into(),
+                Box::new(|app: &mut App| app.ui.datetime_editor.increment(-1)),
+            );
+        }
+
+        map.register(
+            Normal,
+            Char('D').into(),
+            Box::new(|app: &mut App| app.ui.prompt_deadline()),
+        );
+
+        // status line prompts; chars are handled by `Ui::prompt_input`
+        map.register(
+            Prompt,
+            KeyCode::Enter.into(),
+            Box::new(|app: &mut App| app.ui.submit_prompt()),
+        );
+        map.register(
+            Prompt,
+            KeyCode::Esc.into(),
+            Box::new(|app: &mut App| app.ui.cancel_prompt()),
+        );
+        map.register(
+            Prompt,
+            KeyCode::Backspace.into(),
+            Box::new(|app: &mut App| app.ui.prompt_backspace()),
+        );
+
+        map.register(
+            Prompt,
+            KeyEvent::new(Char('r'), KeyModifiers::CONTROL),
+            Box::new(|app: &mut App| app.ui.toggle_search_regex()),
+        );
+
+        map.register(
+            Normal,
+            Char('/').into(),
+            Box::new(|app: &mut App| app.ui.open_search(false)),
+        );
+        map.register(
+            Normal,
+            Char('?').into(),
+            Box::new(|app: &mut App| app.ui.open_search(true)),
+        );
+        map.register(
+            Normal,
+            Char('n').into(),
+            Box::new(|app: &mut App| app.ui.search_next(false)),
+        );
+        map.register(
+            Normal,
+            Char('N').into(),
+            Box::new(|app: &mut App| app.ui.search_next(true)),
+        );
+        map.register(
+            Normal,
+            KeyCode::Esc.into(),
+            Box::new(|app: &mut App| app.ui.clear_search()),
+        );
+
+        for mode in [Normal, Notes] {
+            for key in [
+                Char('u').into(),
+                KeyEvent::new(Char('z'), KeyModifiers::CONTROL),
+            ] {
+                map.register(mode.clone(), key, Box::new(|app: &mut App| app.ui.undo()));
+            }
+            for c in ['r', 'y'] {
+                map.register(
+                    mode.clone(),
+                    KeyEvent::new(Char(c), KeyModifiers::CONTROL),
+                    Box::new(|app: &mut App| app.ui.redo()),
+                );
+            }
+        }
+
         map.register(
             Normal,
             Char('-').into(),
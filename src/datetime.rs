@@ -0,0 +1,227 @@
+use chrono::{Datelike, Duration, Local, Months, NaiveDate, NaiveDateTime, Timelike};
+
+/// editor for deadlines, working on one segment (year, month, ...) at a time
+#[derive(Debug, Clone)]
+pub struct DatetimeEditor {
+    pub value: NaiveDateTime,
+    pub segment: DatetimeSegment,
+    /// digits typed into the current segment so far
+    digits: String,
+}
+
+/// a part of a `(YYYY-mm-dd HH:MM)` deadline
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum DatetimeSegment {
+    #[default]
+    Year,
+    Month,
+    Day,
+    Hour,
+    Minute,
+}
+use DatetimeSegment::*;
+
+impl DatetimeSegment {
+    /// number of digits of the segment
+    pub fn width(self) -> usize {
+        match self {
+            Year => 4,
+            _ => 2,
+        }
+    }
+
+    fn next(self) -> Option<Self> {
+        match self {
+            Year => Some(Month),
+            Month => Some(Day),
+            Day => Some(Hour),
+            Hour => Some(Minute),
+            Minute => None,
+        }
+    }
+
+    fn prev(self) -> Option<Self> {
+        match self {
+            Year => None,
+            Month => Some(Year),
+            Day => Some(Month),
+            Hour => Some(Day),
+            Minute => Some(Hour),
+        }
+    }
+}
+
+impl Default for DatetimeEditor {
+    fn default() -> Self {
+        Self {
+            value: default_deadline(),
+            segment: Year,
+            digits: String::new(),
+        }
+    }
+}
+
+impl DatetimeEditor {
+    /// starts editing `value`, or a default deadline if there is none
+    pub fn start(&mut self, value: Option<NaiveDateTime>) {
+        self.value = value.unwrap_or_else(default_deadline);
+        self.segment = Year;
+        self.digits.clear();
+    }
+
+    /// types `digit` into the current segment. digits that would make the
+    /// deadline invalid (e.g. month 13, february 30th) are ignored.
+    /// once a segment is complete, moves on to the next one
+    pub fn input_digit(&mut self, digit: char) {
+        if !digit.is_ascii_digit() {
+            return;
+        }
+        let width = self.segment.width();
+        let mut digits = self.digits.clone();
+        if digits.len() >= width {
+            digits.clear();
+        }
+        digits.push(digit);
+        let value: u32 = digits.parse().unwrap();
+
+        if digits.len() < width {
+            if value <= self.segment_max() {
+                self.digits = digits;
+            }
+        } else if let Some(new_value) = self.with_segment(self.segment, value) {
+            self.value = new_value;
+            self.digits.clear();
+            if let Some(next) = self.segment.next() {
+                self.segment = next;
+            }
+        }
+    }
+
+    /// moves to the next segment, applying partially typed digits.
+    /// returns `false` if there is no next segment
+    pub fn next_segment(&mut self) -> bool {
+        self.apply_digits();
+        match self.segment.next() {
+            Some(next) => {
+                self.segment = next;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// moves to the previous segment, applying partially typed digits
+    pub fn prev_segment(&mut self) {
+        self.apply_digits();
+        if let Some(prev) = self.segment.prev() {
+            self.segment = prev;
+        }
+    }
+
+    /// adds `delta` units of the current segment, carrying over into the others
+    pub fn increment(&mut self, delta: i64) {
+        self.digits.clear();
+        let months = |n: i64| Months::new(n.unsigned_abs() as u32);
+        let new_value = match (self.segment, delta >= 0) {
+            (Year, true) => self.value.checked_add_months(months(delta * 12)),
+            (Year, false) => self.value.checked_sub_months(months(delta * 12)),
+            (Month, true) => self.value.checked_add_months(months(delta)),
+            (Month, false) => self.value.checked_sub_months(months(delta)),
+            (Day, _) => self.value.checked_add_signed(Duration::days(delta)),
+            (Hour, _) => self.value.checked_add_signed(Duration::hours(delta)),
+            (Minute, _) => self.value.checked_add_signed(Duration::minutes(delta)),
+        };
+        if let Some(new_value) = new_value {
+            self.value = new_value;
+        }
+    }
+
+    /// the deadline with partially typed digits applied, if they are valid
+    pub fn finish(&mut self) -> NaiveDateTime {
+        self.apply_digits();
+        self.value
+    }
+
+    /// `(YYYY-mm-dd HH:MM)` split into parts, each paired with whether it is the
+    /// current segment
+    pub fn render(&self) -> Vec<(String, bool)> {
+        let current = |segment: DatetimeSegment, value: u32| {
+            let text = match segment == self.segment && !self.digits.is_empty() {
+                true => format!("{:_<1$}", self.digits, segment.width()),
+                false => format!("{:01$}", value, segment.width()),
+            };
+            (text, segment == self.segment)
+        };
+        let plain = |s: &str| (s.to_string(), false);
+        vec![
+            plain("("),
+            current(Year, self.value.year() as u32),
+            plain("-"),
+            current(Month, self.value.month()),
+            plain("-"),
+            current(Day, self.value.day()),
+            plain(" "),
+            current(Hour, self.value.hour()),
+            plain(":"),
+            current(Minute, self.value.minute()),
+            plain(")"),
+        ]
+    }
+
+    /// writes partially typed digits into the current segment, if they are valid
+    fn apply_digits(&mut self) {
+        let digits = std::mem::take(&mut self.digits);
+        if let Ok(value) = digits.parse() {
+            if let Some(new_value) = self.with_segment(self.segment, value) {
+                self.value = new_value;
+            }
+        }
+    }
+
+    /// largest valid value of the current segment
+    fn segment_max(&self) -> u32 {
+        match self.segment {
+            Year => 9999,
+            Month => 12,
+            Day => days_in_month(self.value.year(), self.value.month()),
+            Hour => 23,
+            Minute => 59,
+        }
+    }
+
+    /// `self.value` with `segment` set to `value`, if that is a valid datetime.
+    /// changing the year or month clamps the day to the end of the month
+    fn with_segment(&self, segment: DatetimeSegment, value: u32) -> Option<NaiveDateTime> {
+        let v = self.value;
+        let (mut year, mut month, mut day) = (v.year(), v.month(), v.day());
+        let (mut hour, mut minute) = (v.hour(), v.minute());
+        match segment {
+            Year => year = value as i32,
+            Month => month = value,
+            Day => day = value,
+            Hour => hour = value,
+            Minute => minute = value,
+        }
+        if matches!(segment, Year | Month) && (1..=12).contains(&month) {
+            day = day.min(days_in_month(year, month));
+        }
+        if segment == Year && year == 0 {
+            return None;
+        }
+        NaiveDate::from_ymd_opt(year, month, day)?.and_hms_opt(hour, minute, 0)
+    }
+}
+
+/// number of days in the given month
+fn days_in_month(year: i32, month: u32) -> u32 {
+    (28..=31)
+        .rev()
+        .find(|&day| NaiveDate::from_ymd_opt(year, month, day).is_some())
+        .unwrap_or(31)
+}
+
+/// the next full hour from now
+fn default_deadline() -> NaiveDateTime {
+    let now = Local::now().naive_local() + Duration::hours(1);
+    now.date().and_hms_opt(now.hour(), 0, 0).unwrap()
+}
@@ -0,0 +1,545 @@
+use crate::*;
+
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+/// a minimal vim-like editor for a single line of text, e.g. an entry's text
+#[derive(Debug, Default, Clone)]
+pub struct LineEditor {
+    pub text: Vec<char>,
+    /// char index of the cursor. may be `text.len()` in insert/replace mode
+    pub cursor: usize,
+    pub mode: EditMode,
+    /// other end of the selection in visual mode
+    pub visual_anchor: usize,
+    /// text of the last yank/delete, used for pasting
+    pub register: String,
+    /// keys of a not yet completed normal/visual mode command, e.g. `d` or `df`
+    pending: String,
+    undo_stack: Vec<(Vec<char>, usize)>,
+    redo_stack: Vec<(Vec<char>, usize)>,
+}
+
+/// a cursor motion within the line
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Motion {
+    Left,
+    Right,
+    LineStart,
+    FirstNonBlank,
+    LineEnd,
+    WordStart {
+        big: bool,
+    },
+    WordBack {
+        big: bool,
+    },
+    WordEnd {
+        big: bool,
+    },
+    /// `f`/`F`/`t`/`T`
+    Find {
+        target: char,
+        forward: bool,
+        until: bool,
+    },
+}
+
+impl LineEditor {
+    /// starts editing `text` in `mode`, with the cursor at char index `cursor`.
+    /// keeps the register of previous edits
+    pub fn start(&mut self, text: &str, cursor: usize, mode: EditMode) {
+        self.text = text.chars().collect();
+        self.mode = mode;
+        self.pending.clear();
+        self.undo_stack.clear();
+        self.redo_stack.clear();
+        self.cursor = cursor;
+        self.clamp_cursor();
+        if self.mode != EditMode::Normal {
+            self.checkpoint();
+        }
+    }
+
+    pub fn text(&self) -> String {
+        self.text.iter().collect()
+    }
+
+    /// char range of the visual selection
+    pub fn selection(&self) -> Option<(usize, usize)> {
+        match self.mode {
+            EditMode::Visual if !self.text.is_empty() => {
+                let start = self.cursor.min(self.visual_anchor);
+                let end = self.cursor.max(self.visual_anchor) + 1;
+                Some((start, end.min(self.text.len())))
+            }
+            _ => None,
+        }
+    }
+
+    /// applies a key press according to the current mode
+    pub fn handle_key(&mut self, key: KeyEvent) {
+        match self.mode {
+            EditMode::Normal => self.handle_normal_key(key),
+            EditMode::Insert => self.handle_insert_key(key),
+            EditMode::Visual => self.handle_visual_key(key),
+            EditMode::Replace => self.handle_replace_key(key),
+        }
+        self.clamp_cursor();
+    }
+
+    fn handle_normal_key(&mut self, key: KeyEvent) {
+        let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+        let c = match key.code {
+            KeyCode::Char(c) => c,
+            KeyCode::Left => 'h',
+            KeyCode::Right => 'l',
+            KeyCode::Home => '0',
+            KeyCode::End => '$',
+            KeyCode::Delete => 'x',
+            KeyCode::Esc => {
+                self.pending.clear();
+                return;
+            }
+            _ => return,
+        };
+
+        if !self.pending.is_empty() {
+            return self.handle_pending(c);
+        }
+
+        if ctrl {
+            match c {
+                'z' => self.undo(),
+                'r' | 'y' => self.redo(),
+                _ => {}
+            }
+            return;
+        }
+
+        if let Some(motion) = Self::motion_for(c) {
+            self.cursor = self.motion_target(motion);
+            return;
+        }
+
+        let len = self.text.len();
+        match c {
+            'x' if len > 0 => {
+                self.checkpoint();
+                self.delete_range(self.cursor, self.cursor + 1, true);
+            }
+            'X' if self.cursor > 0 => {
+                self.checkpoint();
+                self.delete_range(self.cursor - 1, self.cursor, true);
+                self.cursor -= 1;
+            }
+            'i' => self.enter_insert(self.cursor),
+            'I' => self.enter_insert(self.motion_target(Motion::FirstNonBlank)),
+            'a' => self.enter_insert((self.cursor + 1).min(len)),
+            'A' => self.enter_insert(len),
+            's' => {
+                self.checkpoint();
+                self.delete_range(self.cursor, self.cursor + 1, true);
+                self.mode = EditMode::Insert;
+            }
+            'S' => {
+                self.checkpoint();
+                self.delete_range(0, len, true);
+                self.mode = EditMode::Insert;
+            }
+            'C' | 'D' => {
+                self.checkpoint();
+                self.delete_range(self.cursor, len, true);
+                if c == 'C' {
+                    self.mode = EditMode::Insert;
+                }
+            }
+            'Y' => self.register = self.text(),
+            'p' | 'P' if !self.register.is_empty() => {
+                self.checkpoint();
+                let at = if c == 'p' && len > 0 {
+                    self.cursor + 1
+                } else {
+                    self.cursor
+                };
+                let pasted: Vec<char> = self.register.chars().collect();
+                let count = pasted.len();
+                self.text.splice(at..at, pasted);
+                self.cursor = at + count - 1;
+            }
+            'R' => {
+                self.checkpoint();
+                self.mode = EditMode::Replace;
+            }
+            'v' => {
+                self.visual_anchor = self.cursor;
+                self.mode = EditMode::Visual;
+            }
+            'u' => self.undo(),
+            'd' | 'c' | 'y' | 'r' | 'f' | 'F' | 't' | 'T' => self.pending.push(c),
+            _ => {}
+        }
+    }
+
+    /// continues a multi-key normal mode command such as `dw`, `cc` or `rx`
+    fn handle_pending(&mut self, c: char) {
+        let mut pending = std::mem::take(&mut self.pending);
+        let (operator, find) = match pending.chars().last() {
+            Some('f' | 'F' | 't' | 'T') => {
+                let find = pending.pop();
+                (pending.chars().next(), find)
+            }
+            _ => (pending.chars().next(), None),
+        };
+
+        if operator == Some('r') {
+            if self.cursor < self.text.len() {
+                self.checkpoint();
+                self.text[self.cursor] = c;
+            }
+            return;
+        }
+
+        let motion = match find {
+            Some(kind) => Motion::Find {
+                target: c,
+                forward: kind.is_ascii_lowercase(),
+                until: kind.eq_ignore_ascii_case(&'t'),
+            },
+            None => match Self::motion_for(c) {
+                Some(motion) => motion,
+                None if "fFtT".contains(c) && operator.is_some() => {
+                    self.pending = pending + &c.to_string();
+                    return;
+                }
+                None if operator == Some(c) => {
+                    // doubled operator (`dd`, `cc`, `yy`) applies to the whole line
+                    return self.apply_operator(c, 0, self.text.len());
+                }
+                None => return,
+            },
+        };
+
+        let target = self.motion_target(motion);
+        let Some(operator) = operator.filter(|op| "dcy".contains(*op)) else {
+            // plain `f`/`t` motion
+            self.cursor = target;
+            return;
+        };
+
+        let inclusive = matches!(motion, Motion::WordEnd { .. } | Motion::Find { .. });
+        let (start, mut end) = if target < self.cursor {
+            (target, self.cursor)
+        } else {
+            (self.cursor, target)
+        };
+        if inclusive && target >= self.cursor {
+            end += 1;
+        }
+        // `cw` on a word only changes up to the end of that word, as in vim
+        if let (Motion::WordStart { big }, Some(&c)) = (motion, self.text.get(self.cursor)) {
+            if operator == 'c' && !c.is_whitespace() {
+                let class = char_class(c, big);
+                end = self.cursor + 1;
+                while end < self.text.len() && char_class(self.text[end], big) == class {
+                    end += 1;
+                }
+            }
+        }
+        self.apply_operator(operator, start, end.min(self.text.len()));
+    }
+
+    /// applies `d`elete, `c`hange or `y`ank to the char range `start..end`
+    fn apply_operator(&mut self, operator: char, start: usize, end: usize) {
+        match operator {
+            'y' => {
+                self.register = self.text[start..end].iter().collect();
+                self.cursor = start;
+            }
+            'd' | 'c' => {
+                self.checkpoint();
+                self.delete_range(start, end, true);
+                self.cursor = start;
+                if operator == 'c' {
+                    self.mode = EditMode::Insert;
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn handle_insert_key(&mut self, key: KeyEvent) {
+        let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+        match key.code {
+            KeyCode::Esc => {
+                self.mode = EditMode::Normal;
+                self.cursor = self.cursor.saturating_sub(1);
+            }
+            KeyCode::Char('w') | KeyCode::Char('h') | KeyCode::Backspace if ctrl => {
+                let start = self.motion_target(Motion::WordBack { big: false });
+                self.delete_range(start, self.cursor, false);
+                self.cursor = start;
+            }
+            KeyCode::Char('v') if ctrl => self.insert_str(&self.register.clone()),
+            KeyCode::Insert if key.modifiers.contains(KeyModifiers::SHIFT) => {
+                self.insert_str(&self.register.clone())
+            }
+            KeyCode::Char(_) if ctrl => {}
+            KeyCode::Char(c) => self.insert_str(&c.to_string()),
+            KeyCode::Backspace if self.cursor > 0 => {
+                self.delete_range(self.cursor - 1, self.cursor, false);
+                self.cursor -= 1;
+            }
+            KeyCode::Delete if self.cursor < self.text.len() => {
+                self.delete_range(self.cursor, self.cursor + 1, false)
+            }
+            KeyCode::Left => self.cursor = self.cursor.saturating_sub(1),
+            KeyCode::Right => self.cursor += 1,
+            KeyCode::Home => self.cursor = 0,
+            KeyCode::End => self.cursor = self.text.len(),
+            _ => {}
+        }
+    }
+
+    fn handle_replace_key(&mut self, key: KeyEvent) {
+        match key.code {
+            KeyCode::Esc => {
+                self.mode = EditMode::Normal;
+                self.cursor = self.cursor.saturating_sub(1);
+            }
+            KeyCode::Char(c) if !key.modifiers.contains(KeyModifiers::CONTROL) => {
+                match self.text.get_mut(self.cursor) {
+                    Some(old) => *old = c,
+                    None => self.text.push(c),
+                }
+                self.cursor += 1;
+            }
+            KeyCode::Backspace | KeyCode::Left => self.cursor = self.cursor.saturating_sub(1),
+            KeyCode::Right => self.cursor += 1,
+            _ => {}
+        }
+    }
+
+    fn handle_visual_key(&mut self, key: KeyEvent) {
+        let c = match key.code {
+            KeyCode::Char(c) => c,
+            KeyCode::Left => 'h',
+            KeyCode::Right => 'l',
+            KeyCode::Home => '0',
+            KeyCode::End => '$',
+            KeyCode::Delete => 'd',
+            KeyCode::Esc => 'v',
+            _ => return,
+        };
+
+        if let Some(object) = self.pending.pop() {
+            // `iw`, `aw`, `iW`, `aW`: select (a)round/(i)nside of the current word
+            if c == 'w' || c == 'W' {
+                self.select_word(c == 'W', object == 'a');
+            }
+            return;
+        }
+
+        if let Some(motion) = Self::motion_for(c) {
+            self.cursor = self.motion_target(motion);
+            return;
+        }
+
+        let Some((start, end)) = self.selection() else {
+            self.mode = EditMode::Normal;
+            return;
+        };
+        match c {
+            'v' => self.mode = EditMode::Normal,
+            'o' => std::mem::swap(&mut self.cursor, &mut self.visual_anchor),
+            'i' | 'a' => self.pending.push(c),
+            'd' | 'x' => {
+                self.apply_operator('d', start, end);
+                self.mode = EditMode::Normal;
+            }
+            'c' | 's' => self.apply_operator('c', start, end),
+            'y' => {
+                self.apply_operator('y', start, end);
+                self.mode = EditMode::Normal;
+            }
+            _ => {}
+        }
+    }
+
+    /// selects the word under the cursor, including trailing whitespace if `around`
+    fn select_word(&mut self, big: bool, around: bool) {
+        if self.text.is_empty() {
+            return;
+        }
+        let class = char_class(self.text[self.cursor], big);
+        let mut start = self.cursor;
+        while start > 0 && char_class(self.text[start - 1], big) == class {
+            start -= 1;
+        }
+        let mut end = self.cursor;
+        while end + 1 < self.text.len() && char_class(self.text[end + 1], big) == class {
+            end += 1;
+        }
+        if around {
+            while end + 1 < self.text.len() && self.text[end + 1].is_whitespace() {
+                end += 1;
+            }
+        }
+        self.visual_anchor = start;
+        self.cursor = end;
+    }
+
+    fn motion_for(c: char) -> Option<Motion> {
+        Some(match c {
+            'h' => Motion::Left,
+            'l' | ' ' => Motion::Right,
+            '0' => Motion::LineStart,
+            '^' => Motion::FirstNonBlank,
+            '$' => Motion::LineEnd,
+            'w' | 'W' => Motion::WordStart { big: c == 'W' },
+            'b' | 'B' => Motion::WordBack { big: c == 'B' },
+            'e' | 'E' => Motion::WordEnd { big: c == 'E' },
+            _ => return None,
+        })
+    }
+
+    /// cursor position after applying `motion`
+    fn motion_target(&self, motion: Motion) -> usize {
+        let len = self.text.len();
+        let class = |i: usize, big: bool| char_class(self.text[i], big);
+        let mut i = self.cursor.min(len);
+        match motion {
+            Motion::Left => i.saturating_sub(1),
+            Motion::Right => (i + 1).min(len),
+            Motion::LineStart => 0,
+            Motion::FirstNonBlank => self
+                .text
+                .iter()
+                .position(|c| !c.is_whitespace())
+                .unwrap_or(0),
+            Motion::LineEnd => len,
+            Motion::WordStart { big } => {
+                if i < len {
+                    let start_class = class(i, big);
+                    while i < len && class(i, big) == start_class && start_class != 0 {
+                        i += 1;
+                    }
+                }
+                while i < len && class(i, big) == 0 {
+                    i += 1;
+                }
+                i
+            }
+            Motion::WordBack { big } => {
+                while i > 0 && class(i - 1, big) == 0 {
+                    i -= 1;
+                }
+                if i > 0 {
+                    let word_class = class(i - 1, big);
+                    while i > 0 && class(i - 1, big) == word_class {
+                        i -= 1;
+                    }
+                }
+                i
+            }
+            Motion::WordEnd { big } => {
+                i += 1;
+                while i < len && class(i, big) == 0 {
+                    i += 1;
+                }
+                if i >= len {
+                    return len.saturating_sub(1);
+                }
+                let word_class = class(i, big);
+                while i + 1 < len && class(i + 1, big) == word_class {
+                    i += 1;
+                }
+                i
+            }
+            Motion::Find {
+                target,
+                forward,
+                until,
+            } => {
+                let found = match forward {
+                    true => (i + 1..len).find(|&j| self.text[j] == target),
+                    false => (0..i).rev().find(|&j| self.text[j] == target),
+                };
+                match (found, until) {
+                    (Some(j), false) => j,
+                    (Some(j), true) if forward => j - 1,
+                    (Some(j), true) => j + 1,
+                    (None, _) => i,
+                }
+            }
+        }
+    }
+
+    fn enter_insert(&mut self, cursor: usize) {
+        self.checkpoint();
+        self.cursor = cursor;
+        self.mode = EditMode::Insert;
+    }
+
+    fn insert_str(&mut self, s: &str) {
+        let chars: Vec<char> = s.chars().collect();
+        let count = chars.len();
+        self.text.splice(self.cursor..self.cursor, chars);
+        self.cursor += count;
+    }
+
+    /// removes the chars in `start..end`, optionally yanking them into the register
+    fn delete_range(&mut self, start: usize, end: usize, yank: bool) {
+        let end = end.min(self.text.len());
+        if start >= end {
+            return;
+        }
+        let removed: String = self.text.drain(start..end).collect();
+        if yank {
+            self.register = removed;
+        }
+    }
+
+    /// keeps the cursor on a char in normal/visual mode, or right behind the text
+    /// in insert/replace mode
+    fn clamp_cursor(&mut self) {
+        let max = match self.mode {
+            EditMode::Insert | EditMode::Replace => self.text.len(),
+            _ => self.text.len().saturating_sub(1),
+        };
+        self.cursor = self.cursor.min(max);
+    }
+
+    /// saves the current state for `undo`
+    fn checkpoint(&mut self) {
+        self.undo_stack.push((self.text.clone(), self.cursor));
+        self.redo_stack.clear();
+    }
+
+    pub fn undo(&mut self) {
+        if let Some((text, cursor)) = self.undo_stack.pop() {
+            let current = (std::mem::replace(&mut self.text, text), self.cursor);
+            self.redo_stack.push(current);
+            self.cursor = cursor;
+        }
+    }
+
+    pub fn redo(&mut self) {
+        if let Some((text, cursor)) = self.redo_stack.pop() {
+            let current = (std::mem::replace(&mut self.text, text), self.cursor);
+            self.undo_stack.push(current);
+            self.cursor = cursor;
+        }
+    }
+}
+
+/// classifies chars for word motions: whitespace (0), word chars (1) and
+/// punctuation (2). with `big`, there is no distinction between the latter two
+fn char_class(c: char, big: bool) -> u8 {
+    if c.is_whitespace() {
+        0
+    } else if big || c.is_alphanumeric() || c == '_' {
+        1
+    } else {
+        2
+    }
+}
@@ -0,0 +1,96 @@
+use crate::*;
+
+use std::collections::VecDeque;
+
+/// undo/redo history of a document. every change is recorded as a transaction,
+/// which stores a snapshot of the state before it. transactions can be nested
+/// to group compound edits into a single undo step
+#[derive(Debug)]
+pub struct History {
+    /// maximum number of undo steps kept
+    pub max_len: usize,
+    undo_stack: VecDeque<Snapshot>,
+    redo_stack: Vec<Snapshot>,
+    /// state at the start of the outermost open transaction
+    open: Option<Snapshot>,
+    depth: usize,
+}
+
+/// state of the ui restored by undo/redo
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Snapshot {
+    pub document: Document,
+    pub active_entry_idx: usize,
+}
+
+impl History {
+    pub fn new(max_len: usize) -> Self {
+        Self {
+            max_len,
+            undo_stack: VecDeque::new(),
+            redo_stack: vec![],
+            open: None,
+            depth: 0,
+        }
+    }
+
+    /// opens a transaction starting at `snapshot`. transactions opened while
+    /// another one is open become part of the outer one
+    pub fn begin(&mut self, snapshot: Snapshot) {
+        if self.depth == 0 {
+            self.open = Some(snapshot);
+        }
+        self.depth += 1;
+    }
+
+    /// closes the innermost open transaction. once the outermost one is closed,
+    /// it becomes an undo step, unless `document` is unchanged
+    pub fn end(&mut self, document: &Document) {
+        if self.depth == 0 {
+            return;
+        }
+        self.depth -= 1;
+        if self.depth > 0 {
+            return;
+        }
+        if let Some(snapshot) = self.open.take() {
+            if snapshot.document != *document {
+                self.undo_stack.push_back(snapshot);
+                while self.undo_stack.len() > self.max_len {
+                    self.undo_stack.pop_front();
+                }
+                self.redo_stack.clear();
+            }
+        }
+    }
+
+    pub fn in_transaction(&self) -> bool {
+        self.depth > 0
+    }
+
+    /// returns the state before the last undo step, storing `current` for redo
+    pub fn undo(&mut self, current: Snapshot) -> Option<Snapshot> {
+        if self.in_transaction() {
+            return None;
+        }
+        let snapshot = self.undo_stack.pop_back()?;
+        self.redo_stack.push(current);
+        Some(snapshot)
+    }
+
+    /// returns the state after the last undone step, storing `current` for undo
+    pub fn redo(&mut self, current: Snapshot) -> Option<Snapshot> {
+        if self.in_transaction() {
+            return None;
+        }
+        let snapshot = self.redo_stack.pop()?;
+        self.undo_stack.push_back(current);
+        Some(snapshot)
+    }
+}
+
+impl Default for History {
+    fn default() -> Self {
+        Self::new(200)
+    }
+}
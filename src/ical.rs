@@ -0,0 +1,195 @@
+use crate::*;
+
+use anyhow::{bail, Context};
+use chrono::{DateTime, Local, NaiveDate, NaiveDateTime, NaiveTime, Utc};
+
+/// entries with a deadline as RFC 5545 `VTODO` components of a calendar.
+/// `stamp` is used for the required `DTSTAMP` property and should be the
+/// current time in utc.
+///
+/// uids aren't stored in the markdown, they are derived from the section's name
+/// and the text of the entry and its parents. editing any of these texts, or
+/// reordering entries with the same text, changes the uid, so that calendar
+/// clients see a new todo and keep the old one
+pub fn to_ics(document: &Document, stamp: NaiveDateTime) -> String {
+    let mut lines = vec![
+        "BEGIN:VCALENDAR".to_string(),
+        "VERSION:2.0".to_string(),
+        "PRODID:-//todue//todue//EN".to_string(),
+    ];
+    if let Some(title) = &document.title {
+        lines.push(format!("X-WR-CALNAME:{}", escape(title)));
+    }
+
+    let mut hashes: Vec<u64> = vec![];
+    for path in document.entry_paths(false) {
+        let entry = document.entry(&path).unwrap();
+        let Some(deadline) = entry.deadline else {
+            continue;
+        };
+
+        // the uid stays the same as long as the entry and its parents keep their text
+        let mut key = document.sections[path[0]].name.clone();
+        for len in 3..=path.len() {
+            key += "\n";
+            key += &document.entry(&path[..len]).unwrap().text;
+        }
+        let hash = fnv1a(&key);
+        let uid = match hashes.iter().filter(|&&h| h == hash).count() {
+            0 => format!("{:016x}@todue", hash),
+            duplicates => format!("{:016x}-{}@todue", hash, duplicates + 1),
+        };
+        hashes.push(hash);
+
+        let status = match entry.status {
+            Status::Done => "COMPLETED",
+            Status::Cancelled => "CANCELLED",
+            Status::InProgress => "IN-PROCESS",
+            Status::Open | Status::Deferred => "NEEDS-ACTION",
+        };
+        lines.extend([
+            "BEGIN:VTODO".to_string(),
+            format!("UID:{}", uid),
+            format!("DTSTAMP:{}Z", stamp.format("%Y%m%dT%H%M%S")),
+            format!("SUMMARY:{}", escape(&entry.text)),
+            format!("DUE:{}", deadline.format("%Y%m%dT%H%M%S")),
+            format!("STATUS:{}", status),
+            "END:VTODO".to_string(),
+        ]);
+    }
+    lines.push("END:VCALENDAR".to_string());
+
+    lines.iter().map(|line| fold(line) + "\r\n").collect()
+}
+
+/// a document with an entry for each `VTODO` of the calendar `ics`.
+/// the calendar's name becomes the title. todos without a summary are left out,
+/// as an entry needs some text
+pub fn from_ics(ics: &str) -> anyhow::Result<Document> {
+    let mut document = Document::default();
+    let mut entry: Option<Entry> = None;
+    let mut in_calendar = false;
+
+    for line in unfold(ics) {
+        let Some((name, value)) = line.split_once(':') else {
+            continue;
+        };
+        // parameters like `TZID` or `VALUE=DATE` are not needed
+        let name = name.split(';').next().unwrap_or(name);
+        match (name.to_ascii_uppercase().as_str(), &mut entry) {
+            ("BEGIN", _) if value.eq_ignore_ascii_case("VCALENDAR") => in_calendar = true,
+            ("BEGIN", None) if value.eq_ignore_ascii_case("VTODO") => {
+                entry = Some(Entry::default())
+            }
+            ("END", Some(_)) if value.eq_ignore_ascii_case("VTODO") => {
+                let entry = entry.take().unwrap();
+                if !entry.text.trim().is_empty() {
+                    let section = document.sections.len() - 1;
+                    document.push_entry(section, entry);
+                }
+            }
+            ("X-WR-CALNAME", None) => document.title = Some(unescape(value)),
+            ("SUMMARY", Some(entry)) => {
+                entry.text = unescape(value).replace('\n', " ");
+                entry.parse_tags();
+            }
+            ("STATUS", Some(entry)) => {
+                entry.status = match value.to_ascii_uppercase().as_str() {
+                    "COMPLETED" => Status::Done,
+                    "CANCELLED" => Status::Cancelled,
+                    "IN-PROCESS" => Status::InProgress,
+                    _ => Status::Open,
+                }
+            }
+            ("DUE", Some(entry)) => {
+                let due = parse_date_time(value)
+                    .with_context(|| format!("Invalid due date `{}`", value))?;
+                entry.deadline = Some(due);
+            }
+            _ => (),
+        }
+    }
+    if !in_calendar {
+        bail!("Not an iCalendar file");
+    }
+    Ok(document)
+}
+
+/// parses an iCalendar `DATE-TIME` or `DATE` value. utc times are converted
+/// to local time, times in other time zones are taken as they are.
+/// dates get the same default time (23:59) as natural language deadlines
+fn parse_date_time(value: &str) -> anyhow::Result<NaiveDateTime> {
+    let value = value.trim();
+    if !value.contains('T') {
+        let date = NaiveDate::parse_from_str(value, "%Y%m%d")?;
+        return Ok(date.and_time(NaiveTime::from_hms_opt(23, 59, 0).unwrap()));
+    }
+    match value.strip_suffix('Z') {
+        Some(utc) => {
+            let utc = NaiveDateTime::parse_from_str(utc, "%Y%m%dT%H%M%S")?;
+            let utc = DateTime::<Utc>::from_naive_utc_and_offset(utc, Utc);
+            Ok(utc.with_timezone(&Local).naive_local())
+        }
+        None => Ok(NaiveDateTime::parse_from_str(value, "%Y%m%dT%H%M%S")?),
+    }
+}
+
+/// escapes a `TEXT` value
+fn escape(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace(';', "\\;")
+        .replace(',', "\\,")
+        .replace('\n', "\\n")
+}
+
+fn unescape(text: &str) -> String {
+    let mut unescaped = String::with_capacity(text.len());
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            unescaped.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n' | 'N') => unescaped.push('\n'),
+            Some(c) => unescaped.push(c),
+            None => unescaped.push('\\'),
+        }
+    }
+    unescaped
+}
+
+/// splits `line` into lines of at most 75 bytes, continued by a leading space
+fn fold(line: &str) -> String {
+    let mut folded = String::with_capacity(line.len());
+    let mut len = 0;
+    for c in line.chars() {
+        if len + c.len_utf8() > 75 {
+            folded += "\r\n ";
+            len = 1;
+        }
+        folded.push(c);
+        len += c.len_utf8();
+    }
+    folded
+}
+
+/// joins folded lines
+fn unfold(ics: &str) -> Vec<String> {
+    let mut lines: Vec<String> = vec![];
+    for line in ics.lines() {
+        let line = line.strip_suffix('\r').unwrap_or(line);
+        match (line.strip_prefix([' ', '\t']), lines.last_mut()) {
+            (Some(continued), Some(last)) => *last += continued,
+            _ => lines.push(line.to_string()),
+        }
+    }
+    lines
+}
+
+/// 64 bit FNV-1a hash, which unlike `std`'s hashers is stable across releases
+fn fnv1a(s: &str) -> u64 {
+    s.bytes().fold(0xcbf29ce484222325, |hash, byte| {
+        (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+    })
+}
@@ -0,0 +1,295 @@
+use crate::*;
+
+use anyhow::{bail, Context};
+use chrono::{NaiveDate, NaiveDateTime};
+use serde::{Deserialize, Serialize};
+
+/// a document as exported to json. only the entries' content is part of the
+/// schema, how they were written in markdown is kept in the optional `format`
+/// fields, which importers can leave out
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+struct JsonDocument {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    title: Option<String>,
+    sections: Vec<JsonSection>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    format: Option<DocumentFormat>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+struct DocumentFormat {
+    indent_width: usize,
+    crlf: bool,
+    missing_final_newline: bool,
+}
+
+/// a section; the one before the first heading has no `name`
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+struct JsonSection {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    level: Option<usize>,
+    entries: Vec<JsonEntry>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    format: Option<SectionFormat>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+struct SectionFormat {
+    /// the heading line, verbatim
+    heading: Option<String>,
+    lines: Vec<Line>,
+}
+
+/// a line of a section or below an entry, in the order they were written
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum Line {
+    Title(String),
+    Text(String),
+    /// the next of the section's `entries` (or the entry's `children`)
+    Entry,
+}
+
+/// an entry. deadlines and timestamps are ISO 8601, e.g. `2024-06-20T16:00:00`
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+struct JsonEntry {
+    text: String,
+    status: Status,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    deadline: Option<NaiveDateTime>,
+    /// `A` (highest) to `Z`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    priority: Option<char>,
+    /// taken from `text` on import
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    tags: Vec<String>,
+    /// taken from `text` on import
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    assignees: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    created: Option<NaiveDateTime>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    completed: Option<NaiveDateTime>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    scheduled: Option<NaiveDate>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    start: Option<NaiveDate>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    recurrence: Option<Recurrence>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    notes: Vec<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    children: Vec<JsonEntry>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    format: Option<EntryFormat>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+struct EntryFormat {
+    /// the entry's line, reused as long as the entry isn't changed
+    #[serde(skip_serializing_if = "Option::is_none")]
+    source: Option<String>,
+    indent: String,
+    marker: ListMarker,
+    dialect: Dialect,
+    priority_style: PriorityStyle,
+    /// notes and subtasks in the order they were written
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    lines: Vec<Line>,
+}
+
+/// `document` as pretty-printed json
+pub fn to_json(document: &Document) -> anyhow::Result<String> {
+    let json = JsonDocument {
+        title: document.title.clone(),
+        sections: document.sections.iter().map(section_to_json).collect(),
+        format: Some(DocumentFormat {
+            indent_width: document.indent_width,
+            crlf: document.crlf,
+            missing_final_newline: document.missing_final_newline,
+        }),
+    };
+    Ok(serde_json::to_string_pretty(&json)?)
+}
+
+fn section_to_json(section: &Section) -> JsonSection {
+    let (entries, lines) = blocks_to_json(&section.blocks);
+    JsonSection {
+        name: section.heading.as_ref().map(|_| section.name.clone()),
+        level: section.heading.as_ref().map(|_| section.level),
+        entries,
+        format: Some(SectionFormat {
+            heading: section.heading.clone(),
+            lines,
+        }),
+    }
+}
+
+/// the entries among `blocks`, and the layout of all of them
+fn blocks_to_json(blocks: &[Block]) -> (Vec<JsonEntry>, Vec<Line>) {
+    let mut entries = vec![];
+    let lines = blocks
+        .iter()
+        .map(|block| match block {
+            Block::Title(line) => Line::Title(line.clone()),
+            Block::Text(line) => Line::Text(line.clone()),
+            Block::Entry(entry) => {
+                entries.push(entry_to_json(entry));
+                Line::Entry
+            }
+        })
+        .collect();
+    (entries, lines)
+}
+
+fn entry_to_json(entry: &Entry) -> JsonEntry {
+    let (children, lines) = blocks_to_json(&entry.children);
+    JsonEntry {
+        text: entry.text.clone(),
+        status: entry.status,
+        deadline: entry.deadline,
+        priority: entry.priority,
+        tags: entry.tags.clone(),
+        assignees: entry.assignees.clone(),
+        created: entry.created,
+        completed: entry.completed,
+        scheduled: entry.scheduled,
+        start: entry.start,
+        recurrence: entry.recurrence,
+        notes: entry.notes(),
+        children,
+        format: Some(EntryFormat {
+            source: entry.source.clone(),
+            indent: entry.indent.clone(),
+            marker: entry.marker,
+            dialect: entry.dialect,
+            priority_style: entry.priority_style,
+            lines,
+        }),
+    }
+}
+
+/// parses a document from json as written by `to_json`. all fields are optional;
+/// without `format`, sections get a heading generated from `name` and `level`,
+/// and subtasks and notes are indented one level below their entry. tags and
+/// assignees are taken from the entries' text
+pub fn from_json(json: &str) -> anyhow::Result<Document> {
+    let json: JsonDocument = serde_json::from_str(json).context("Invalid document json")?;
+    let mut document = Document {
+        title: json.title,
+        sections: vec![],
+        ..Default::default()
+    };
+    if let Some(format) = json.format {
+        document.indent_width = format.indent_width.max(1);
+        document.crlf = format.crlf;
+        document.missing_final_newline = format.missing_final_newline;
+    }
+    let indent_width = document.indent_width;
+    for section in json.sections {
+        let (heading, lines) = match section.format {
+            Some(format) => (format.heading, format.lines),
+            None => (None, vec![]),
+        };
+        let name = section.name.unwrap_or_default();
+        let level = match (section.level, name.is_empty()) {
+            (Some(level), _) => level.clamp(1, 6),
+            (None, true) => 0,
+            (None, false) => 2,
+        };
+        let heading = match heading {
+            Some(heading) => Some(heading),
+            None if !name.is_empty() => Some(format!("{} {}", "#".repeat(level), name)),
+            None => None,
+        };
+        let entries = section
+            .entries
+            .into_iter()
+            .map(|entry| entry_from_json(entry, String::new(), indent_width))
+            .collect::<anyhow::Result<_>>()?;
+        document.sections.push(Section {
+            heading,
+            name,
+            level,
+            blocks: layout(entries, lines),
+        });
+    }
+    if document
+        .sections
+        .first()
+        .is_none_or(|s| s.heading.is_some())
+    {
+        document.sections.insert(0, Section::default());
+    }
+    Ok(document)
+}
+
+/// the entry described by `json`, indented by `indent` unless its format says otherwise
+fn entry_from_json(json: JsonEntry, indent: String, indent_width: usize) -> anyhow::Result<Entry> {
+    if let Some(priority) = json.priority.filter(|p| !p.is_ascii_uppercase()) {
+        bail!("Invalid priority `{}`, expected `A` to `Z`", priority);
+    }
+    let format = json.format.unwrap_or_else(|| EntryFormat {
+        indent,
+        ..Default::default()
+    });
+    let mut entry = Entry {
+        marker: format.marker,
+        status: json.status,
+        text: json.text,
+        deadline: json.deadline,
+        priority: json.priority,
+        priority_style: format.priority_style,
+        created: json.created,
+        completed: json.completed,
+        scheduled: json.scheduled,
+        start: json.start,
+        recurrence: json.recurrence,
+        dialect: format.dialect,
+        source: format.source,
+        indent: format.indent,
+        ..Default::default()
+    };
+    entry.parse_tags();
+    let children = json
+        .children
+        .into_iter()
+        .map(|child| {
+            let indent = entry.indent.clone() + &" ".repeat(indent_width);
+            entry_from_json(child, indent, indent_width)
+        })
+        .collect::<anyhow::Result<_>>()?;
+    entry.children = layout(children, format.lines);
+    if entry.notes() != json.notes {
+        entry.set_notes(&json.notes, indent_width);
+    }
+    Ok(entry)
+}
+
+/// `entries` placed among the other `lines`. entries without a place in
+/// `lines` are added after the last one
+fn layout(entries: Vec<Entry>, lines: Vec<Line>) -> Vec<Block> {
+    let mut entries = entries.into_iter();
+    let mut blocks: Vec<Block> = lines
+        .into_iter()
+        .filter_map(|line| match line {
+            Line::Title(line) => Some(Block::Title(line)),
+            Line::Text(line) => Some(Block::Text(line)),
+            Line::Entry => entries.next().map(Block::Entry),
+        })
+        .collect();
+    let idx = blocks
+        .iter()
+        .rposition(|b| matches!(b, Block::Entry(_)))
+        .map_or(blocks.len(), |i| i + 1);
+    blocks.splice(idx..idx, entries.map(Block::Entry));
+    blocks
+}
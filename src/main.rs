@@ -4,7 +4,7 @@ pub use std::{
     path, process, time,
 };
 
-use crossterm::event::{poll, read, Event::*, KeyCode::Char};
+use crossterm::event::{poll, read, Event::*, KeyCode, KeyCode::Char, KeyModifiers};
 
 mod app;
 use app::*;
@@ -12,13 +12,41 @@ use app::*;
 mod control;
 use control::*;
 
+mod editor;
+use editor::*;
+
+mod datetime;
+use datetime::*;
+
+mod history;
+use history::*;
+
+mod search;
+use search::*;
+
+mod view;
+use view::*;
+
+mod tags;
+use tags::*;
+
 mod md;
 use md::*;
 
+mod todotxt;
+use todotxt::*;
+
+mod json;
+
+mod ical;
+
 mod ui;
 use ui::*;
 
 mod cli;
+use cli::FileFormat;
+
+mod commands;
 
 mod log;
 pub use log::*;
@@ -26,7 +54,18 @@ pub use log::*;
 mod tests;
 
 fn main() -> anyhow::Result<()> {
-    let mut app = App::init()?;
+    let args = cli::new().get_matches();
+    if let Some((command, command_args)) = args.subcommand() {
+        // subcommands work on the file directly, without the tui
+        let md_file = cli::md_file(&args);
+        if let Err(e) = commands::run(command, &md_file, command_args) {
+            Log::error_exit_with(ErrorCode::App, e);
+        }
+        Log::flush();
+        return Ok(());
+    }
+
+    let mut app = App::init(&args)?;
 
     let _guard = DropGuard {
         // clean up terminal state even on panics
@@ -55,7 +94,8 @@ fn main() -> anyhow::Result<()> {
             ));
             process::exit(ErrorCode::IO.into());
         }
-        write!(file.unwrap(), "{}", app.ui.document.to_md()).unwrap_or_else(|e| {
+        let contents = FileFormat::of(&app.md_file).serialize(&app.ui.document);
+        write!(file.unwrap(), "{}", contents).unwrap_or_else(|e| {
             Log::error(format!("Failed to write to file: `{}`: {}", app.md_file, e));
             process::exit(ErrorCode::IO.into());
         });
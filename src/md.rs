@@ -1,23 +1,238 @@
-use anyhow::anyhow;
-use chrono::NaiveDateTime;
+use crate::{find_tags, MoveDirection, TagKind};
+
+use anyhow::{anyhow, bail};
+use chrono::{Datelike, Duration, Months, NaiveDate, NaiveDateTime, NaiveTime, Timelike, Weekday};
+use serde::{Deserialize, Serialize};
 
 /// a todo-list entry; can be thought of as an abstract representation of a line
 /// of markdown in one of the following formats:
 /// - without deadline: `"- [ ] Do something"`
 /// - with deadline: `"- [ ] (2024-06-20 20:00) Do another thing"`
+/// - in the obsidian tasks dialect: `"- [ ] Do another thing ⏫ 📅 2024-06-20"`
+///
+/// instead of `-`, entries may use any other list marker (`*`, `+`, `1.`, `1)`)
 #[derive(Debug, Default, Clone, PartialEq, Eq)]
 pub struct Entry {
-    pub done: bool,
+    /// the list marker in front of the checkbox
+    pub marker: ListMarker,
+    pub status: Status,
     pub text: String,
     pub deadline: Option<NaiveDateTime>,
+    /// `A` (highest) to `Z`
+    pub priority: Option<char>,
+    /// how `priority` is written in markdown
+    pub priority_style: PriorityStyle,
+    /// when the entry was added (`created:2024-06-20T09:00` or `➕`)
+    pub created: Option<NaiveDateTime>,
+    /// when the entry was marked done (`done:2024-06-21T10:00` or `✅`)
+    pub completed: Option<NaiveDateTime>,
+    /// date the entry is planned to be worked on (`⏳`)
+    pub scheduled: Option<NaiveDate>,
+    /// date the entry can be started on (`🛫`)
+    pub start: Option<NaiveDate>,
+    /// how often the entry repeats, e.g. `every week` (`🔁`)
+    pub recurrence: Option<Recurrence>,
+    /// how the deadline is written in markdown
+    pub dialect: Dialect,
+    /// `#tags` in the text, without the `#`. the text keeps them
+    pub tags: Vec<String>,
+    /// `@assignees` in the text, without the `@`
+    pub assignees: Vec<String>,
+    /// the line this entry was parsed from, if any. used to write back
+    /// unmodified entries exactly as they were
+    pub source: Option<String>,
+    /// leading whitespace of the entry's line
+    pub indent: String,
+    /// blocks indented below this entry, i.e. subtasks and notes
+    pub children: Vec<Block>,
+    /// whether `children` are hidden in the tui
+    pub collapsed: bool,
+}
+
+/// state of an entry, written as the character between its brackets
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Status {
+    /// `[ ]`
+    #[default]
+    Open,
+    /// `[x]` or `[X]`
+    Done,
+    /// `[-]`
+    Cancelled,
+    /// `[/]`
+    InProgress,
+    /// `[>]`
+    Deferred,
+}
+
+impl Status {
+    /// the status written as `c` between the brackets
+    pub fn from_char(c: char) -> Option<Self> {
+        match c {
+            ' ' => Some(Status::Open),
+            'x' | 'X' => Some(Status::Done),
+            '-' => Some(Status::Cancelled),
+            '/' => Some(Status::InProgress),
+            '>' => Some(Status::Deferred),
+            _ => None,
+        }
+    }
+
+    pub fn to_char(self) -> char {
+        match self {
+            Status::Open => ' ',
+            Status::Done => 'x',
+            Status::Cancelled => '-',
+            Status::InProgress => '/',
+            Status::Deferred => '>',
+        }
+    }
+
+    /// the status' name as in json, e.g. `in-progress`
+    pub fn name(self) -> &'static str {
+        match self {
+            Status::Open => "open",
+            Status::Done => "done",
+            Status::Cancelled => "cancelled",
+            Status::InProgress => "in-progress",
+            Status::Deferred => "deferred",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        [
+            Status::Open,
+            Status::Done,
+            Status::Cancelled,
+            Status::InProgress,
+            Status::Deferred,
+        ]
+        .into_iter()
+        .find(|status| status.name() == name)
+    }
+
+    /// the status following this one in `cycle`, or the first one of `cycle`
+    /// if this status isn't part of it
+    pub fn next_in(self, cycle: &[Status]) -> Self {
+        match cycle.iter().position(|&s| s == self) {
+            Some(i) => cycle[(i + 1) % cycle.len()],
+            None => cycle.first().copied().unwrap_or(self),
+        }
+    }
+
+    /// whether nothing is left to do, i.e. the entry is done or cancelled
+    pub fn is_closed(self) -> bool {
+        matches!(self, Status::Done | Status::Cancelled)
+    }
+}
+
+/// a rule for repeating an entry, like `every 2 weeks` or `every month on the 1st`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct Recurrence {
+    /// number of `unit`s between two occurrences
+    pub every: u32,
+    pub unit: RecurrenceUnit,
+    /// whether the next occurrence is counted from completing the entry
+    /// rather than from its deadline (`every week when done`)
+    pub when_done: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RecurrenceUnit {
+    Day,
+    /// monday to friday
+    Weekday,
+    /// optionally on a fixed day of the week
+    Week(Option<Weekday>),
+    /// optionally on a fixed day of the month
+    Month(Option<u32>),
+    Year,
+}
+
+/// the list marker of an entry's line
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub enum ListMarker {
+    /// `-`
+    #[default]
+    Dash,
+    /// `*`
+    Star,
+    /// `+`
+    Plus,
+    /// `1.` or `1)`. the number is kept consecutive within the entry's list
+    Ordered { number: u32, delimiter: char },
+}
+
+/// syntax of an entry's priority in markdown
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PriorityStyle {
+    /// `(A)` in front of the text, like in todo.txt
+    #[default]
+    Letter,
+    /// `!!!`, `!!` or `!` in front of the text for priorities `A` to `C`
+    Bangs,
+    /// `[#A]` in front of the text, like in org-mode
+    Org,
+    /// obsidian tasks' `🔺`, `⏫`, `🔼`, `🔽` or `⏬` after the text
+    Emoji,
+}
+
+/// syntax of an entry's deadline in markdown. other metadata (priority,
+/// scheduled date, ...) is always written as obsidian tasks' emoji markers
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Dialect {
+    /// `(2024-06-20 20:00)` in front of the text
+    #[default]
+    Todue,
+    /// `📅 2024-06-20` after the text, without a time
+    Obsidian,
 }
 
 /// a todo-document; can be thought of as an abstract representation of an entire
-/// document consisting of `title`, and all of the documents `entries`
-#[derive(Debug, Default, Clone, PartialEq, Eq)]
+/// document consisting of `title`, and all of the documents `sections` in order.
+/// lines that todue doesn't interpret are kept as-is, so that writing back a
+/// document only changes the entries that were actually modified
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Document {
     pub title: Option<String>,
-    pub entries: Vec<Entry>,
+    /// always contains at least the (unnamed) section preceding the first heading
+    pub sections: Vec<Section>,
+    /// indentation width (in columns) of subtasks, detected from the markdown
+    pub indent_width: usize,
+    pub crlf: bool,
+    pub missing_final_newline: bool,
+}
+
+/// a named group of entries; everything from a heading (other than the title)
+/// up to the next heading
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Section {
+    /// the heading line, kept verbatim. `None` for the part before the first heading
+    pub heading: Option<String>,
+    pub name: String,
+    pub level: usize,
+    pub blocks: Vec<Block>,
+}
+
+/// location of an entry within a document: index into `Document::sections`,
+/// index into the section's `blocks`, followed by indices into the `children`
+/// of each entry along the way
+pub type EntryPath = Vec<usize>;
+
+/// a single line-based block of a todo-document
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Block {
+    /// the line containing the documents title, kept verbatim
+    Title(String),
+    /// any line that isn't a heading or an entry (prose, code, blank lines, ...),
+    /// kept verbatim
+    Text(String),
+    Entry(Entry),
 }
 
 pub trait Markdown {
@@ -29,53 +244,64 @@ pub trait Markdown {
 
 impl Markdown for Entry {
     fn to_md(&self) -> String {
-        let mut md = format!("- [{}] ", if self.done { "x" } else { " " });
-        match self.deadline {
-            Some(deadline) => md += &format!("{} ", deadline.format("(%Y-%m-%d %H:%M)")),
-            None => md += &str::repeat(" ", "(YYYY-mm-dd HH:MM) ".len()),
+        let mut md = format!("{} [{}] ", self.marker, self.status.to_char());
+        if self.dialect == Dialect::Todue {
+            match self.deadline {
+                Some(deadline) => md += &format!("{} ", deadline.format("(%Y-%m-%d %H:%M)")),
+                None => md += &str::repeat(" ", "(YYYY-mm-dd HH:MM) ".len()),
+            }
         }
+        md += &self.priority_marker();
         md += &self.text;
+        md += &self.emoji_metadata();
+        md += &self.timestamps();
         md
     }
 
     fn from_md(md: String) -> anyhow::Result<Self> {
         let mut entry = Entry::default();
 
+        // like commonmark, only indentation may precede the list marker, and
+        // whitespace has to follow it
+        let line = md.trim_start();
+        let (marker, rest) = line
+            .split_once(|c: char| c.is_whitespace())
+            .ok_or_else(|| anyhow!("Invalid entry"))?;
+        entry.marker = marker.parse()?;
+        let md = rest.trim_start();
+        if !md.starts_with('[') {
+            bail!("Invalid entry, expected a checkbox after the list marker");
+        }
+
         #[derive(PartialEq, Eq)]
         enum ParseState {
-            Nothing,
-            Dash,
+            Marker,
             BrackOpen,
             BrackClose,
             Text,
         }
         use ParseState::*;
 
-        let mut state = Nothing;
+        let mut state = Marker;
         let mut i = 0;
         let mut text = String::new();
 
         while i < md.len() {
+            // the (possibly multi-byte) character at `i`
+            let c = &md[i..i + md[i..].chars().next().unwrap().len_utf8()];
             match state {
-                Nothing if &md[i..=i] == "-" => state = Dash,
-
-                Dash if &md[i..=i] == "[" => state = BrackOpen,
+                Marker if c == "[" => state = BrackOpen,
 
-                BrackOpen if &md[i..=i] == "x" => entry.done = true,
+                BrackOpen if c == "]" => state = BrackClose,
 
-                BrackOpen if &md[i..=i] == "]" => state = BrackClose,
-
-                BrackOpen
-                    if !&md[i..=i]
-                        .chars()
-                        .next()
-                        .unwrap_or(' ')
-                        .is_ascii_whitespace() =>
-                {
-                    return Err(anyhow!("Invalid character in presumed checkbox. Expected whitespace or 'x', found '{}'", &md[i..=i]));
+                BrackOpen if !c.chars().next().unwrap_or(' ').is_ascii_whitespace() => {
+                    match c.chars().next().and_then(Status::from_char) {
+                        Some(status) => entry.status = status,
+                        None => return Err(anyhow!("Invalid character in presumed checkbox. Expected whitespace, 'x', 'X', '-', '/' or '>', found '{}'", c)),
+                    }
                 }
 
-                BrackClose if md.len() - i >= "(YYYY-mm-dd HH:MM)".len() => {
+                BrackClose if md.get(i..i + "(YYYY-mm-dd HH:MM)".len()).is_some() => {
                     let maybe_date = &md[i..i + "(YYYY-mm-dd HH:MM)".len()];
                     let deadline = NaiveDateTime::parse_from_str(maybe_date, "(%Y-%m-%d %H:%M)");
                     if maybe_date.chars().any(|c| c.is_alphabetic()) {
@@ -88,7 +314,7 @@ impl Markdown for Entry {
                                 i += "(YYYY-mm-dd HH:MM)".len();
                                 text = String::new();
                             }
-                            _ => text += &md[i..=i],
+                            _ => text += c,
                         }
                     }
                 }
@@ -96,20 +322,28 @@ impl Markdown for Entry {
                 Text | BrackClose
                     if !&md[i..].chars().next().unwrap_or(' ').is_ascii_whitespace() =>
                 {
-                    text += &md[i..=i];
+                    text += c;
                     state = Text;
                 }
 
-                Text if !["\r", "\n"].contains(&&md[i..=i]) => text += &md[i..=i],
+                Text if !["\r", "\n"].contains(&c) => text += c,
 
                 _ => {}
             }
-            i += 1;
+            i += c.len();
         }
 
         match state {
             Text => {
                 entry.text = text.trim_start().to_string();
+                if let Some((priority, style, rest)) = parse_priority_marker(&entry.text) {
+                    entry.priority = Some(priority);
+                    entry.priority_style = style;
+                    entry.text = rest.to_string();
+                }
+                let entry = with_timestamps(entry);
+                let mut entry = with_emoji_metadata(&entry).unwrap_or(entry);
+                entry.parse_tags();
                 Ok(entry)
             }
             _ => Err(anyhow!("Invalid entry")),
@@ -119,45 +353,1335 @@ impl Markdown for Entry {
 
 impl Markdown for Document {
     fn to_md(&self) -> String {
-        let mut md = match &self.title {
-            None => String::new(),
-            Some(title) => format!("# {}\n", title),
-        };
+        let newline = if self.crlf { "\r\n" } else { "\n" };
+        let mut lines = vec![];
 
-        if !self.entries.is_empty() {
-            md += "\n";
-            for entry in &self.entries {
-                md += &(entry.to_md() + "\n");
+        if let Some(title) = &self.title {
+            let blocks = self.sections.iter().flat_map(|s| &s.blocks);
+            if !blocks.clone().any(|b| matches!(b, Block::Title(_))) {
+                lines.push(format!("# {}", title));
+                if blocks.count() > 0 || self.sections.len() > 1 {
+                    lines.push(String::new());
+                }
             }
         }
 
+        for section in &self.sections {
+            if let Some(heading) = &section.heading {
+                lines.push(heading.clone());
+            }
+            push_block_lines(&section.blocks, &mut lines);
+        }
+
+        let mut md = lines.join(newline);
+        if !lines.is_empty() && !self.missing_final_newline {
+            md += newline;
+        }
         md
     }
 
     fn from_md(md: String) -> anyhow::Result<Self> {
-        let mut document = Document::default();
+        let mut document = Document {
+            crlf: md.find('\n').is_some_and(|i| md[..i].ends_with('\r')),
+            missing_final_newline: !md.is_empty() && !md.ends_with('\n'),
+            ..Default::default()
+        };
+
+        // indent widths and block indices of the entries the next line may be nested in
+        let mut open: Vec<(usize, usize)> = vec![];
+        let mut blank_lines = vec![];
+        let mut fence: Option<&str> = None;
+        let mut indent_width: Option<usize> = None;
+
         for line in md.lines() {
-            if document.title.is_none() {
-                let mut chars = line.chars().peekable();
-                let mut count = 0;
-                while chars.peek().is_some_and(|c| c.is_ascii_whitespace()) {
-                    chars.next();
+            let level = heading_level(line);
+            let indent = indent_width_of(line);
+            let trimmed = line.trim_start();
+            let mut block = Block::Text(line.to_string());
+
+            if let Some(marker) = fence {
+                // inside fenced code blocks, nothing is interpreted
+                if trimmed.starts_with(marker) {
+                    fence = None;
+                }
+            } else if trimmed.is_empty() {
+                // blank lines belong to wherever the next line ends up
+                blank_lines.push(block);
+                continue;
+            } else if let Some(marker) = ["```", "~~~"].iter().find(|m| trimmed.starts_with(*m)) {
+                fence = Some(marker);
+                close_entries(&mut open, indent);
+            } else if document.title.is_none() && level == Some(1) {
+                document.title = Some(heading_text(line).to_string());
+                block = Block::Title(line.to_string());
+                open.clear();
+            } else if let Some(level) = level {
+                let section = document.sections.last_mut().unwrap();
+                section.blocks.append(&mut blank_lines);
+                document.sections.push(Section {
+                    heading: Some(line.to_string()),
+                    name: heading_text(line).to_string(),
+                    level,
+                    blocks: vec![],
+                });
+                open.clear();
+                continue;
+            } else if let Ok(mut entry) = Entry::from_md(line.to_string()) {
+                close_entries(&mut open, indent);
+                if let Some(&(parent_indent, _)) = open.last() {
+                    let width = indent - parent_indent;
+                    indent_width = Some(indent_width.map_or(width, |w| w.min(width)));
+                }
+                entry.source = Some(line.to_string());
+                entry.indent = line[..line.len() - trimmed.len()].to_string();
+                block = Block::Entry(entry);
+            } else {
+                close_entries(&mut open, indent);
+            }
+
+            let is_entry = matches!(block, Block::Entry(_));
+            let section = document.sections.last_mut().unwrap();
+            let container = container_mut(&mut section.blocks, &open);
+            container.append(&mut blank_lines);
+            container.push(block);
+            if is_entry {
+                open.push((indent, container.len() - 1));
+            }
+        }
+        let section = document.sections.last_mut().unwrap();
+        section.blocks.append(&mut blank_lines);
+
+        if let Some(width) = indent_width {
+            document.indent_width = width;
+        }
+
+        // entries without a deadline are written like most of the others
+        let paths = document.entry_paths(false);
+        let entries = paths.iter().map(|path| document.entry(path).unwrap());
+        let obsidian = entries
+            .filter(|entry| entry.deadline.is_some())
+            .map(|entry| match entry.dialect {
+                Dialect::Obsidian => 1,
+                Dialect::Todue => -1,
+            })
+            .sum::<i32>();
+        if obsidian > 0 {
+            for path in paths {
+                let entry = document.entry_mut(&path).unwrap();
+                if entry.deadline.is_none() {
+                    entry.dialect = Dialect::Obsidian;
+                }
+            }
+        }
+        Ok(document)
+    }
+}
+
+impl Default for Document {
+    fn default() -> Self {
+        Self {
+            title: None,
+            sections: vec![Section::default()],
+            indent_width: 2,
+            crlf: false,
+            missing_final_newline: false,
+        }
+    }
+}
+
+impl Entry {
+    /// like `Entry::to_md`, but includes the entry's indentation and reuses the
+    /// line the entry was parsed from, as long as the entry hasn't been modified since
+    pub fn to_md_preserving_source(&self) -> String {
+        let md = self.to_md();
+        // without a deadline, the line itself doesn't tell its dialect
+        let unchanged = |mut source: Entry| {
+            if source.deadline.is_none() {
+                source.dialect = self.dialect;
+            }
+            source.to_md() == md
+        };
+        match &self.source {
+            Some(source)
+                if source[..source.len() - source.trim_start().len()] == self.indent
+                    && Entry::from_md(source.clone()).is_ok_and(unchanged) =>
+            {
+                source.clone()
+            }
+            _ => self.indent.clone() + &md,
+        }
+    }
+
+    /// the entry's priority as written in front of its text, e.g. `(A) `.
+    /// empty for emoji priorities, which are written after the text
+    fn priority_marker(&self) -> String {
+        let Some(priority) = self.priority else {
+            return String::new();
+        };
+        match (self.priority_style, priority) {
+            (PriorityStyle::Emoji, _) => String::new(),
+            (PriorityStyle::Bangs, 'A') => "!!! ".into(),
+            (PriorityStyle::Bangs, 'B') => "!! ".into(),
+            (PriorityStyle::Bangs, 'C') => "! ".into(),
+            (PriorityStyle::Letter, 'A'..='C') => format!("({}) ", priority),
+            // `(D)` wouldn't be read back as a priority
+            _ => format!("[#{}] ", priority),
+        }
+    }
+
+    /// the entry's metadata as obsidian tasks' emoji markers, in the order
+    /// obsidian writes them, e.g. ` ⏫ 🔁 every week 📅 2024-06-20`
+    fn emoji_metadata(&self) -> String {
+        let mut md = String::new();
+        if let (Some(priority), PriorityStyle::Emoji) = (self.priority, self.priority_style) {
+            let marker = PRIORITY_MARKERS
+                .iter()
+                .find(|(_, p)| priority <= *p)
+                .map_or('⏬', |(m, _)| *m);
+            md += &format!(" {}", marker);
+        }
+        if let Some(recurrence) = &self.recurrence {
+            md += &format!(" 🔁 {}", recurrence);
+        }
+        // the todue dialect writes timestamps with their time, see `timestamps`
+        let obsidian = |date: Option<NaiveDateTime>| match self.dialect {
+            Dialect::Obsidian => date.map(|d| d.date()),
+            Dialect::Todue => None,
+        };
+        let dates = [
+            ('➕', obsidian(self.created)),
+            ('🛫', self.start),
+            ('⏳', self.scheduled),
+            ('📅', obsidian(self.deadline)),
+            ('✅', obsidian(self.completed)),
+        ];
+        for (marker, date) in dates {
+            if let Some(date) = date {
+                md += &format!(" {} {}", marker, date.format("%Y-%m-%d"));
+            }
+        }
+        md
+    }
+
+    /// the entry's timestamps in the todue dialect, e.g. ` done:2024-06-21T10:00`.
+    /// timestamps at midnight are written as dates, as they usually only are one
+    fn timestamps(&self) -> String {
+        if self.dialect == Dialect::Obsidian {
+            return String::new();
+        }
+        let mut md = String::new();
+        for (key, stamp) in [("created", self.created), ("done", self.completed)] {
+            match stamp {
+                Some(stamp) if stamp.time() == NaiveTime::MIN => {
+                    md += &format!(" {}:{}", key, stamp.format("%Y-%m-%d"))
                 }
-                while chars.peek().is_some_and(|&c| c == '#') {
-                    count += 1;
-                    chars.next();
+                Some(stamp) => md += &format!(" {}:{}", key, stamp.format("%Y-%m-%dT%H:%M")),
+                None => {}
+            }
+        }
+        md
+    }
+
+    /// sets the status, stamping the time the entry is completed at, or
+    /// clearing it again if the entry isn't done anymore
+    pub fn set_status(&mut self, status: Status, now: NaiveDateTime) {
+        match status {
+            Status::Done if self.status != Status::Done => self.completed = Some(stamp(now)),
+            Status::Done => {}
+            _ => self.completed = None,
+        }
+        self.status = status;
+    }
+
+    /// marks the entry and its subtasks as open again, e.g. for the next
+    /// occurrence of a recurring entry
+    fn reopen(&mut self) {
+        self.status = Status::Open;
+        self.completed = None;
+        self.source = None;
+        for block in &mut self.children {
+            if let Block::Entry(child) = block {
+                child.reopen();
+            }
+        }
+    }
+
+    /// fills `tags` and `assignees` from the text. has to be called whenever
+    /// the text changes
+    pub fn parse_tags(&mut self) {
+        self.tags.clear();
+        self.assignees.clear();
+        for tag in find_tags(&self.text) {
+            let names = match tag.kind {
+                TagKind::Tag => &mut self.tags,
+                TagKind::Assignee => &mut self.assignees,
+            };
+            if !names.contains(&tag.name) {
+                names.push(tag.name);
+            }
+        }
+    }
+
+    /// whether any of the entry's children is an entry itself
+    pub fn has_subtasks(&self) -> bool {
+        self.children.iter().any(|b| matches!(b, Block::Entry(_)))
+    }
+
+    /// whether the entry has any notes
+    pub fn has_notes(&self) -> bool {
+        self.children
+            .iter()
+            .any(|b| matches!(b, Block::Text(line) if !line.trim().is_empty()))
+    }
+
+    /// the lines indented below the entry that aren't subtasks (continuation
+    /// paragraphs, sub-bullets, links, ...), without their common indentation
+    pub fn notes(&self) -> Vec<String> {
+        let lines: Vec<&str> = self
+            .note_slots()
+            .into_iter()
+            .filter_map(|i| match &self.children[i] {
+                Block::Text(line) => Some(line.as_str()),
+                _ => None,
+            })
+            .collect();
+        let indent = lines
+            .iter()
+            .filter(|line| !line.trim().is_empty())
+            .map(|line| line.len() - line.trim_start().len())
+            .min()
+            .unwrap_or(0);
+        lines
+            .iter()
+            .map(|line| line.get(indent..).unwrap_or("").trim_end().to_string())
+            .collect()
+    }
+
+    /// indices of the children holding the entry's notes. blank lines only
+    /// belong to the notes if they separate two of them, others (e.g. between
+    /// subtasks) are left alone when editing the notes
+    fn note_slots(&self) -> Vec<usize> {
+        let mut slots = vec![];
+        let mut blanks = vec![];
+        let mut in_notes = false;
+        for (i, block) in self.children.iter().enumerate() {
+            match block {
+                Block::Text(line) if line.trim().is_empty() => {
+                    if in_notes {
+                        blanks.push(i);
+                    }
                 }
-                while chars.peek().is_some_and(|c| c.is_ascii_whitespace()) {
-                    chars.next();
+                Block::Text(_) => {
+                    slots.append(&mut blanks);
+                    slots.push(i);
+                    in_notes = true;
                 }
-                if count == 1 {
-                    document.title = Some(chars.collect());
+                _ => {
+                    blanks.clear();
+                    in_notes = false;
                 }
             }
-            if let Ok(entry) = Entry::from_md(line.to_string()) {
-                document.entries.push(entry);
+        }
+        slots
+    }
+
+    /// replaces the entry's notes with `notes`, touching only the lines that
+    /// changed. new lines are indented like the other notes, or one level
+    /// (`indent_width`) below the entry if it has none
+    pub fn set_notes(&mut self, notes: &[String], indent_width: usize) {
+        let old = self.notes();
+        let slots = self.note_slots();
+        let prefix = old.iter().zip(notes).take_while(|(a, b)| a == b).count();
+        let suffix = old[prefix..]
+            .iter()
+            .rev()
+            .zip(notes[prefix..].iter().rev())
+            .take_while(|(a, b)| a == b)
+            .count();
+        let indent = self.notes_indent(indent_width);
+
+        let removed = &slots[prefix..old.len() - suffix];
+        let at = match (removed.first(), prefix.checked_sub(1)) {
+            (Some(&first), _) => first,
+            (None, Some(last)) => slots[last] + 1,
+            (None, None) => slots.first().copied().unwrap_or(0),
+        };
+        for &i in removed.iter().rev() {
+            self.children.remove(i);
+        }
+        let lines = notes[prefix..notes.len() - suffix]
+            .iter()
+            .map(|line| match line.trim_end() {
+                "" => Block::Text(String::new()),
+                line => Block::Text(format!("{}{}", indent, line)),
+            });
+        self.children.splice(at..at, lines);
+    }
+
+    /// the indentation of the entry's notes, or one level below the entry if it has none
+    fn notes_indent(&self, indent_width: usize) -> String {
+        self.note_slots()
+            .into_iter()
+            .filter_map(|i| match &self.children[i] {
+                Block::Text(line) if !line.trim().is_empty() => {
+                    Some(&line[..line.len() - line.trim_start().len()])
+                }
+                _ => None,
+            })
+            .min_by_key(|indent| indent.len())
+            .map_or(
+                self.indent.clone() + &" ".repeat(indent_width),
+                str::to_string,
+            )
+    }
+}
+
+impl Document {
+    /// paths of all entries in document order. children of collapsed entries
+    /// are left out if `skip_collapsed` is set
+    pub fn entry_paths(&self, skip_collapsed: bool) -> Vec<EntryPath> {
+        fn collect(blocks: &[Block], path: &mut EntryPath, paths: &mut Vec<EntryPath>, skip: bool) {
+            for (i, block) in blocks.iter().enumerate() {
+                if let Block::Entry(entry) = block {
+                    path.push(i);
+                    paths.push(path.clone());
+                    if !(skip && entry.collapsed) {
+                        collect(&entry.children, path, paths, skip);
+                    }
+                    path.pop();
+                }
             }
         }
-        Ok(document)
+        let mut paths = vec![];
+        for (i, section) in self.sections.iter().enumerate() {
+            collect(&section.blocks, &mut vec![i], &mut paths, skip_collapsed);
+        }
+        paths
+    }
+
+    pub fn entry(&self, path: &[usize]) -> Option<&Entry> {
+        let (section, path) = path.split_first()?;
+        let (last, parents) = path.split_last()?;
+        let mut blocks = &self.sections.get(*section)?.blocks;
+        for &i in parents {
+            match blocks.get(i) {
+                Some(Block::Entry(entry)) => blocks = &entry.children,
+                _ => return None,
+            }
+        }
+        match blocks.get(*last) {
+            Some(Block::Entry(entry)) => Some(entry),
+            _ => None,
+        }
+    }
+
+    pub fn entry_mut(&mut self, path: &[usize]) -> Option<&mut Entry> {
+        let (last, parents) = path.split_last()?;
+        match self.siblings_mut(parents)?.get_mut(*last) {
+            Some(Block::Entry(entry)) => Some(entry),
+            _ => None,
+        }
+    }
+
+    /// the blocks containing the entry at `parent` + `[i]`, i.e. the section's
+    /// blocks for a single-element `parent` path, or the parent entry's children otherwise
+    fn siblings_mut(&mut self, parent: &[usize]) -> Option<&mut Vec<Block>> {
+        let (section, parent) = parent.split_first()?;
+        let mut blocks = &mut self.sections.get_mut(*section)?.blocks;
+        for &i in parent {
+            match blocks.get_mut(i) {
+                Some(Block::Entry(entry)) => blocks = &mut entry.children,
+                _ => return None,
+            }
+        }
+        Some(blocks)
+    }
+
+    /// inserts `entry` at `path`, shifting the block there (and all following
+    /// ones) back. returns the path of the inserted entry
+    pub fn insert_entry(&mut self, path: &[usize], entry: Entry) -> Option<EntryPath> {
+        let (&idx, parent) = path.split_last()?;
+        let siblings = self.siblings_mut(parent)?;
+        if idx > siblings.len() {
+            return None;
+        }
+        let starts = list_starts(siblings);
+        siblings.insert(idx, Block::Entry(entry));
+        renumber(siblings, &starts);
+        Some(path.to_vec())
+    }
+
+    /// appends `entry` after the last top-level entry of `section`, with the same
+    /// indentation, list marker and dialect. returns the path of the appended entry
+    pub fn push_entry(&mut self, section: usize, mut entry: Entry) -> Option<EntryPath> {
+        let blocks = &mut self.sections.get_mut(section)?.blocks;
+        let starts = list_starts(blocks);
+        let idx = match entry_slots(blocks).last() {
+            Some(&last) => {
+                if let Block::Entry(sibling) = &blocks[last] {
+                    entry.indent = sibling.indent.clone();
+                    entry.marker = sibling.marker;
+                    entry.dialect = sibling.dialect;
+                }
+                last + 1
+            }
+            // below the blank line following a heading, if any
+            None => blocks
+                .iter()
+                .rposition(|b| !matches!(b, Block::Text(line) if line.trim().is_empty()))
+                .map_or(blocks.len().min(1), |i| i + 1),
+        };
+        blocks.insert(idx, Block::Entry(entry));
+        renumber(blocks, &starts);
+        Some(vec![section, idx])
     }
+
+    /// makes all entries write their deadline in `dialect`
+    pub fn set_dialect(&mut self, dialect: Dialect) {
+        for path in self.entry_paths(false) {
+            let entry = self.entry_mut(&path).unwrap();
+            if entry.dialect != dialect {
+                entry.dialect = dialect;
+                // rewritten in the new dialect even if nothing else changed
+                entry.source = None;
+            }
+        }
+    }
+
+    /// repeats the completed, recurring entry at `path`: an open copy with the
+    /// next deadline is inserted in front of it, and the completed entry is
+    /// moved to the `Archive` section if `archive` is set. returns the copy's path
+    pub fn respawn_recurring(
+        &mut self,
+        path: &[usize],
+        archive: bool,
+        now: NaiveDateTime,
+    ) -> Option<EntryPath> {
+        let entry = self.entry(path)?;
+        let recurrence = entry.recurrence?;
+        let end_of_day = NaiveTime::from_hms_opt(23, 59, 0).unwrap();
+        let base = match entry.deadline {
+            Some(deadline) if !recurrence.when_done => deadline,
+            Some(deadline) => NaiveDateTime::new(now.date(), deadline.time()),
+            None => NaiveDateTime::new(now.date(), end_of_day),
+        };
+        let next = recurrence.next(base);
+
+        let mut copy = entry.clone();
+        copy.reopen();
+        copy.created = copy.created.map(|_| stamp(now));
+        copy.deadline = Some(next);
+        // other dates keep their distance to the deadline
+        let shift = next.date() - base.date();
+        copy.scheduled = copy.scheduled.map(|date| date + shift);
+        copy.start = copy.start.map(|date| date + shift);
+        self.insert_entry(path, copy)?;
+
+        if archive {
+            let mut completed_path = path.to_vec();
+            *completed_path.last_mut().unwrap() += 1;
+            let mut completed = self.remove_entry(&completed_path)?;
+            let section = match self.section_idx(ARCHIVE) {
+                Some(section) => section,
+                None => self.push_section(ARCHIVE),
+            };
+            if !self.sections[section]
+                .blocks
+                .iter()
+                .any(|b| matches!(b, Block::Entry(_)))
+            {
+                completed.indent = String::new();
+            }
+            self.push_entry(section, completed);
+        }
+        Some(path.to_vec())
+    }
+
+    /// appends a section with a heading of the same level as the topmost
+    /// sections, so that it isn't nested in the last one. returns its index
+    pub fn push_section(&mut self, name: &str) -> usize {
+        let level = self
+            .sections
+            .iter()
+            .filter(|section| section.heading.is_some())
+            .map(|section| section.level)
+            .min()
+            .unwrap_or(2);
+        let last = self.sections.last_mut().unwrap();
+        if last
+            .blocks
+            .last()
+            .is_some_and(|b| !matches!(b, Block::Text(line) if line.trim().is_empty()))
+        {
+            last.blocks.push(Block::Text(String::new()));
+        }
+        self.sections.push(Section {
+            heading: Some(format!("{} {}", "#".repeat(level), name)),
+            name: name.to_string(),
+            level,
+            blocks: vec![Block::Text(String::new())],
+        });
+        self.sections.len() - 1
+    }
+
+    /// the section new entries go to unless another one is given: the last
+    /// one that isn't the `Archive`
+    pub fn default_section(&self) -> usize {
+        self.sections
+            .iter()
+            .rposition(|section| {
+                section.heading.is_none() || !section.name.eq_ignore_ascii_case(ARCHIVE)
+            })
+            .unwrap_or(0)
+    }
+
+    /// index of the section named `name`, ignoring case
+    pub fn section_idx(&self, name: &str) -> Option<usize> {
+        self.sections.iter().position(|section| {
+            section.heading.is_some() && section.name.eq_ignore_ascii_case(name.trim())
+        })
+    }
+
+    /// removes the entry at `path` including its subtasks
+    pub fn remove_entry(&mut self, path: &[usize]) -> Option<Entry> {
+        let (&idx, parent) = path.split_last()?;
+        let siblings = self.siblings_mut(parent)?;
+        let starts = list_starts(siblings);
+        let entry = match siblings.get(idx) {
+            Some(Block::Entry(_)) => match siblings.remove(idx) {
+                Block::Entry(entry) => entry,
+                _ => unreachable!(),
+            },
+            _ => return None,
+        };
+        renumber(siblings, &starts);
+        Some(entry)
+    }
+
+    /// replaces the notes of the entry at `path` with `notes`, see `Entry::set_notes`
+    pub fn set_notes(&mut self, path: &[usize], notes: &[String]) -> Option<()> {
+        let indent_width = self.indent_width;
+        self.entry_mut(path)?.set_notes(notes, indent_width);
+        Some(())
+    }
+
+    /// swaps the entry at `path` (including its subtasks) with its next/previous
+    /// sibling entry. other blocks stay in place. top-level entries at the edge of
+    /// their section move on into the neighbouring section, all others wrap around.
+    /// returns the entry's new path
+    pub fn move_entry(&mut self, path: &[usize], dir: MoveDirection) -> Option<EntryPath> {
+        let (&idx, parent) = path.split_last()?;
+        let siblings = self.siblings_mut(parent)?;
+        let slots = entry_slots(siblings);
+        let pos = slots.iter().position(|&slot| slot == idx)?;
+
+        let at_edge = match dir {
+            MoveDirection::Down => pos == slots.len() - 1,
+            MoveDirection::Up => pos == 0,
+        };
+        if at_edge && parent.len() == 1 {
+            if let Some(new_path) = self.move_entry_to_neighbour_section(parent[0], idx, dir) {
+                return Some(new_path);
+            }
+        }
+
+        let siblings = self.siblings_mut(parent)?;
+        let other = match dir {
+            MoveDirection::Down => slots[(pos + 1) % slots.len()],
+            MoveDirection::Up => slots[(pos + slots.len() - 1) % slots.len()],
+        };
+        let starts = list_starts(siblings);
+        siblings.swap(idx, other);
+        renumber(siblings, &starts);
+
+        let mut new_path = parent.to_vec();
+        new_path.push(other);
+        Some(new_path)
+    }
+
+    /// moves the top-level entry at `idx` of section `section` into the following
+    /// (`Down`) or preceding (`Up`) section, next to the entries already in there
+    fn move_entry_to_neighbour_section(
+        &mut self,
+        section: usize,
+        idx: usize,
+        dir: MoveDirection,
+    ) -> Option<EntryPath> {
+        let target = match dir {
+            MoveDirection::Down => section + 1,
+            MoveDirection::Up => section.checked_sub(1)?,
+        };
+        if target >= self.sections.len() {
+            return None;
+        }
+
+        let blocks = &mut self.sections[section].blocks;
+        let starts = list_starts(blocks);
+        let block = blocks.remove(idx);
+        renumber(blocks, &starts);
+
+        let blocks = &mut self.sections[target].blocks;
+        let starts = list_starts(blocks);
+        let slots = entry_slots(blocks);
+        let insert_idx = match (dir, slots.first(), slots.last()) {
+            (MoveDirection::Down, Some(&first), _) => first,
+            (MoveDirection::Up, _, Some(&last)) => last + 1,
+            _ => blocks
+                .iter()
+                .rposition(|b| !matches!(b, Block::Text(line) if line.trim().is_empty()))
+                .map_or(blocks.len().min(1), |i| i + 1),
+        };
+        blocks.insert(insert_idx, block);
+        renumber(blocks, &starts);
+        Some(vec![target, insert_idx])
+    }
+}
+
+/// name of the section completed recurring entries are moved to
+const ARCHIVE: &str = "Archive";
+
+/// obsidian tasks' priority markers from highest to lowest, with the
+/// todo.txt-style priority they are read as
+const PRIORITY_MARKERS: [(char, char); 5] = [
+    ('🔺', 'A'),
+    ('⏫', 'B'),
+    ('🔼', 'C'),
+    ('🔽', 'D'),
+    ('⏬', 'E'),
+];
+
+/// `entry` with the `created:` and `done:` timestamps at the end of its text
+/// moved into the respective fields
+fn with_timestamps(mut entry: Entry) -> Entry {
+    while let Some((rest, last)) = entry.text.rsplit_once(' ') {
+        let Some((key, value)) = last.split_once(':') else {
+            break;
+        };
+        let stamp = NaiveDateTime::parse_from_str(value, "%Y-%m-%dT%H:%M")
+            .ok()
+            .or_else(|| {
+                let date = NaiveDate::parse_from_str(value, "%Y-%m-%d").ok()?;
+                Some(date.and_time(NaiveTime::MIN))
+            });
+        match (key, stamp) {
+            ("created", Some(stamp)) => entry.created = Some(stamp),
+            ("done", Some(stamp)) => entry.completed = Some(stamp),
+            _ => break,
+        }
+        entry.text = rest.trim_end().to_string();
+    }
+    entry
+}
+
+/// `now` as a timestamp, i.e. without seconds
+pub fn stamp(now: NaiveDateTime) -> NaiveDateTime {
+    now.with_second(0)
+        .and_then(|d| d.with_nanosecond(0))
+        .unwrap_or(now)
+}
+
+/// a priority marker (`(A)`, `[#A]`, `!!!`, `!!` or `!`) at the start of
+/// `text`, followed by the text after it
+fn parse_priority_marker(text: &str) -> Option<(char, PriorityStyle, &str)> {
+    let (marker, rest) = text.split_once(' ')?;
+    let rest = rest.trim_start();
+    if rest.is_empty() {
+        return None;
+    }
+    let letter = |inner: Option<&str>| {
+        let mut chars = inner?.chars();
+        let letter = chars.next().filter(char::is_ascii_uppercase)?;
+        chars.next().is_none().then_some(letter)
+    };
+    // other letters in parentheses are more likely prose, like `(I) Introduction`
+    let parens = marker
+        .strip_prefix('(')
+        .and_then(|m| m.strip_suffix(')'))
+        .filter(|m| ["A", "B", "C"].contains(m));
+    let org = marker.strip_prefix("[#").and_then(|m| m.strip_suffix(']'));
+    let (priority, style) = match marker {
+        "!!!" => ('A', PriorityStyle::Bangs),
+        "!!" => ('B', PriorityStyle::Bangs),
+        "!" => ('C', PriorityStyle::Bangs),
+        _ if letter(parens).is_some() => (letter(parens)?, PriorityStyle::Letter),
+        _ => (letter(org)?, PriorityStyle::Org),
+    };
+    Some((priority, style, rest))
+}
+
+/// `entry` with obsidian tasks' emoji metadata (`⏫ 🔁 every week 📅 2024-06-20`)
+/// moved from the end of its text into the respective fields. `None` if the
+/// text has no metadata or any of it is malformed, so that it stays text
+fn with_emoji_metadata(entry: &Entry) -> Option<Entry> {
+    let is_marker = |c: char| {
+        ['📅', '⏳', '🛫', '✅', '➕', '🔁'].contains(&c)
+            || PRIORITY_MARKERS.iter().any(|(m, _)| *m == c)
+    };
+    let start = entry.text.find(is_marker)?;
+    let mut parsed = Entry {
+        text: entry.text[..start].trim_end().to_string(),
+        ..entry.clone()
+    };
+
+    let mut rest = &entry.text[start..];
+    while let Some(marker) = rest.chars().next() {
+        let after = &rest[marker.len_utf8()..];
+        let end = after.find(is_marker).unwrap_or(after.len());
+        let value = after[..end].trim_matches(|c: char| c.is_whitespace() || c == '\u{fe0f}');
+        rest = &after[end..];
+
+        let date = || NaiveDate::parse_from_str(value, "%Y-%m-%d").ok();
+        match marker {
+            // a parenthesised deadline takes precedence, keep the marker as text
+            '📅' if entry.deadline.is_some() => return None,
+            // only the deadline tells the dialect, other metadata looks the same in both
+            '📅' => {
+                parsed.deadline = Some(date()?.and_hms_opt(23, 59, 0)?);
+                parsed.dialect = Dialect::Obsidian;
+            }
+            '⏳' => parsed.scheduled = Some(date()?),
+            '🛫' => parsed.start = Some(date()?),
+            '✅' => parsed.completed = Some(date()?.and_time(NaiveTime::MIN)),
+            '➕' => parsed.created = Some(date()?.and_time(NaiveTime::MIN)),
+            '🔁' => parsed.recurrence = Some(value.parse().ok()?),
+            // a priority in front of the text takes precedence as well
+            _ if entry.priority.is_some() => return None,
+            _ if value.is_empty() => {
+                parsed.priority_style = PriorityStyle::Emoji;
+                parsed.priority = PRIORITY_MARKERS
+                    .iter()
+                    .find(|(m, _)| *m == marker)
+                    .map(|(_, p)| *p)
+            }
+            _ => return None,
+        }
+    }
+    Some(parsed)
+}
+
+/// appends the markdown lines of `blocks` and all their children to `lines`
+fn push_block_lines(blocks: &[Block], lines: &mut Vec<String>) {
+    for block in blocks {
+        match block {
+            Block::Title(line) | Block::Text(line) => lines.push(line.clone()),
+            Block::Entry(entry) => {
+                lines.push(entry.to_md_preserving_source());
+                push_block_lines(&entry.children, lines);
+            }
+        }
+    }
+}
+
+/// indices of all entries in `blocks`
+fn entry_slots(blocks: &[Block]) -> Vec<usize> {
+    blocks
+        .iter()
+        .enumerate()
+        .filter(|(_, block)| matches!(block, Block::Entry(_)))
+        .map(|(i, _)| i)
+        .collect()
+}
+
+/// the blocks that a line nested in the `open` entries is appended to
+fn container_mut<'a>(blocks: &'a mut Vec<Block>, open: &[(usize, usize)]) -> &'a mut Vec<Block> {
+    match open.split_first() {
+        None => blocks,
+        Some((&(_, i), rest)) => match &mut blocks[i] {
+            Block::Entry(entry) => container_mut(&mut entry.children, rest),
+            _ => unreachable!("only entries can contain nested blocks"),
+        },
+    }
+}
+
+/// closes all `open` entries that a line indented by `indent` can't be nested in
+fn close_entries(open: &mut Vec<(usize, usize)>, indent: usize) {
+    while open
+        .last()
+        .is_some_and(|&(open_indent, _)| open_indent >= indent)
+    {
+        open.pop();
+    }
+}
+
+/// the first number of each ordered list among `blocks`
+fn list_starts(blocks: &[Block]) -> Vec<u32> {
+    ordered_lists(blocks)
+        .iter()
+        .filter_map(|list| match &blocks[list[0]] {
+            Block::Entry(entry) => match entry.marker {
+                ListMarker::Ordered { number, .. } => Some(number),
+                _ => None,
+            },
+            _ => None,
+        })
+        .collect()
+}
+
+/// numbers the entries of each ordered list among `blocks` consecutively,
+/// counting from `starts` (as returned by `list_starts` before changing them).
+/// if a number gets more or fewer digits (`9.` to `10.`), the entry's subtasks
+/// and notes are shifted along, so that they stay nested below it
+fn renumber(blocks: &mut [Block], starts: &[u32]) {
+    for (i, list) in ordered_lists(blocks).into_iter().enumerate() {
+        let mut next = None;
+        for idx in list {
+            if let Block::Entry(entry) = &mut blocks[idx] {
+                if let ListMarker::Ordered { number, .. } = &mut entry.marker {
+                    let start = starts.get(i).copied().unwrap_or(*number);
+                    let old = *number;
+                    *number = next.unwrap_or(start);
+                    next = Some(*number + 1);
+                    // only the digits change, the rest of the line is kept as written
+                    if *number != old {
+                        let new = number.to_string();
+                        if let Some(source) = &mut entry.source {
+                            let at = source.len() - source.trim_start().len();
+                            let digits = source[at..].find(|c: char| !c.is_ascii_digit());
+                            source.replace_range(at..at + digits.unwrap_or(0), &new);
+                        }
+                        let width = old.to_string().len();
+                        shift_indent(&mut entry.children, new.len() as isize - width as isize);
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// indents all lines of `blocks` (recursively) by `by` more spaces, or removes
+/// up to `-by` leading spaces from them. blank lines stay as they are
+fn shift_indent(blocks: &mut [Block], by: isize) {
+    let shift = |line: &mut String| match by >= 0 {
+        true => line.insert_str(0, &" ".repeat(by as usize)),
+        false => {
+            let spaces = line.len() - line.trim_start_matches(' ').len();
+            line.drain(..spaces.min(by.unsigned_abs()));
+        }
+    };
+    for block in blocks {
+        match block {
+            Block::Text(line) if !line.trim().is_empty() => shift(line),
+            Block::Entry(entry) => {
+                shift(&mut entry.indent);
+                if let Some(source) = &mut entry.source {
+                    shift(source);
+                }
+                shift_indent(&mut entry.children, by);
+            }
+            _ => {}
+        }
+    }
+}
+
+/// block indices of the runs of ordered entries among `blocks`. other entries
+/// and lines other than blank ones end a run
+fn ordered_lists(blocks: &[Block]) -> Vec<Vec<usize>> {
+    let mut lists: Vec<Vec<usize>> = vec![];
+    let mut in_list = false;
+    for (idx, block) in blocks.iter().enumerate() {
+        match block {
+            Block::Entry(entry) if matches!(entry.marker, ListMarker::Ordered { .. }) => {
+                match (in_list, lists.last_mut()) {
+                    (true, Some(list)) => list.push(idx),
+                    _ => lists.push(vec![idx]),
+                }
+                in_list = true;
+            }
+            Block::Text(line) if line.trim().is_empty() => {}
+            _ => in_list = false,
+        }
+    }
+    lists
+}
+
+/// width of the leading whitespace of `line` in columns, counting tabs as 4
+fn indent_width_of(line: &str) -> usize {
+    line.chars()
+        .take_while(|c| c.is_whitespace())
+        .map(|c| if c == '\t' { 4 } else { 1 })
+        .sum()
+}
+
+/// returns the level of the atx-heading on `line`, if it is one
+fn heading_level(line: &str) -> Option<usize> {
+    let trimmed = line.trim_start();
+    let level = trimmed.chars().take_while(|&c| c == '#').count();
+    let rest = &trimmed[level..];
+    match level {
+        1..=6 if rest.is_empty() || rest.starts_with(char::is_whitespace) => Some(level),
+        _ => None,
+    }
+}
+
+/// returns the text of the atx-heading on `line`
+fn heading_text(line: &str) -> &str {
+    line.trim_start().trim_start_matches('#').trim()
+}
+
+/// parses a deadline from either the canonical `YYYY-mm-dd HH:MM` form (optionally
+/// in parentheses) or a relative/natural description relative to `now`, e.g.
+/// `tomorrow 9am`, `fri`, `in 3d`, `next monday 14:00` or `eod`.
+///
+/// weekdays refer to their next occurrence (today included), `next <weekday>`
+/// to the one after today. deadlines without a time are due at the end of the day
+pub fn parse_deadline(input: &str, now: NaiveDateTime) -> anyhow::Result<NaiveDateTime> {
+    let end_of_day = NaiveTime::from_hms_opt(23, 59, 0).unwrap();
+    parse_datetime(input, now, end_of_day)
+}
+
+/// like `parse_deadline`, but dates without a time are at `default_time`
+pub fn parse_datetime(
+    input: &str,
+    now: NaiveDateTime,
+    default_time: NaiveTime,
+) -> anyhow::Result<NaiveDateTime> {
+    let input = input.trim();
+    let canonical = input.trim_start_matches('(').trim_end_matches(')');
+    if let Ok(deadline) = NaiveDateTime::parse_from_str(canonical, "%Y-%m-%d %H:%M") {
+        return Ok(deadline);
+    }
+
+    let end_of_day = NaiveTime::from_hms_opt(23, 59, 0).unwrap();
+    let today = now.date();
+    let lowercase = input.to_lowercase();
+    let tokens: Vec<&str> = lowercase.split_whitespace().collect();
+    if tokens.is_empty() {
+        bail!("Empty deadline");
+    }
+
+    let mut date: Option<NaiveDate> = None;
+    let mut time: Option<NaiveTime> = None;
+    let mut offset_base: Option<NaiveDateTime> = None;
+
+    let mut i = 0;
+    while i < tokens.len() {
+        let token = tokens[i];
+        let next_token = tokens.get(i + 1).copied();
+        match token {
+            "at" | "on" | "by" => {}
+            "today" => date = Some(today),
+            "tomorrow" | "tmr" | "tmrw" => date = Some(today + Duration::days(1)),
+            "now" => {
+                date = Some(today);
+                time = Some(now.time());
+            }
+            "noon" => time = NaiveTime::from_hms_opt(12, 0, 0),
+            "midnight" => time = NaiveTime::from_hms_opt(0, 0, 0),
+            "eod" => time = Some(end_of_day),
+            "eow" => {
+                date = Some(next_weekday(today, Weekday::Sun, false));
+                time = Some(end_of_day);
+            }
+            "eom" => {
+                let first_of_month = today.with_day(1).unwrap();
+                date = Some(first_of_month + Months::new(1) - Duration::days(1));
+                time = Some(end_of_day);
+            }
+            "next" => {
+                i += 1;
+                date = Some(match next_token {
+                    Some("week") => today + Duration::weeks(1),
+                    Some("month") => today + Months::new(1),
+                    Some("year") => today + Months::new(12),
+                    Some(day) => match day.parse::<Weekday>() {
+                        Ok(weekday) => next_weekday(today, weekday, true),
+                        Err(_) => bail!("Expected weekday after `next`, found `{}`", day),
+                    },
+                    None => bail!("Expected weekday after `next`"),
+                });
+            }
+            "in" => {
+                let (amount, unit, consumed) = match next_token {
+                    Some(t) if t.chars().all(|c| c.is_ascii_digit()) => {
+                        (t, tokens.get(i + 2).copied().unwrap_or(""), 2)
+                    }
+                    Some(t) => {
+                        let digits = t.chars().take_while(|c| c.is_ascii_digit()).count();
+                        (&t[..digits], &t[digits..], 1)
+                    }
+                    None => bail!("Expected duration after `in`"),
+                };
+                let amount: u32 = amount
+                    .parse()
+                    .map_err(|_| anyhow!("Invalid duration amount `{}`", amount))?;
+                let base = offset_base.unwrap_or(now);
+                let n = amount as i64;
+                offset_base = Some(match unit {
+                    "m" | "min" | "mins" | "minute" | "minutes" => base + Duration::minutes(n),
+                    "h" | "hr" | "hrs" | "hour" | "hours" => base + Duration::hours(n),
+                    "d" | "day" | "days" => base + Duration::days(n),
+                    "w" | "wk" | "wks" | "week" | "weeks" => base + Duration::weeks(n),
+                    "mo" | "month" | "months" => base + Months::new(amount),
+                    "y" | "yr" | "yrs" | "year" | "years" => base + Months::new(amount * 12),
+                    _ => bail!("Unknown duration unit `{}`", unit),
+                });
+                i += consumed;
+            }
+            _ => {
+                if let Ok(weekday) = token.parse::<Weekday>() {
+                    date = Some(next_weekday(today, weekday, false));
+                } else if let Ok(parsed) = NaiveDate::parse_from_str(token, "%Y-%m-%d") {
+                    date = Some(parsed);
+                } else if let Some(parsed) = parse_time_of_day(token, next_token) {
+                    time = Some(parsed);
+                    if matches!(next_token, Some("am" | "pm")) {
+                        i += 1;
+                    }
+                } else {
+                    bail!("Couldn't understand `{}` in deadline `{}`", token, input);
+                }
+            }
+        }
+        i += 1;
+    }
+
+    let deadline = match (offset_base, date, time) {
+        (Some(base), date, time) => {
+            NaiveDateTime::new(date.unwrap_or(base.date()), time.unwrap_or(base.time()))
+        }
+        (None, Some(date), time) => NaiveDateTime::new(date, time.unwrap_or(default_time)),
+        (None, None, Some(time)) => {
+            // a time that already passed today most likely refers to tomorrow
+            let deadline = NaiveDateTime::new(today, time);
+            match deadline < now {
+                true => deadline + Duration::days(1),
+                false => deadline,
+            }
+        }
+        (None, None, None) => bail!("Couldn't understand deadline `{}`", input),
+    };
+    Ok(deadline
+        .with_second(0)
+        .and_then(|d| d.with_nanosecond(0))
+        .unwrap_or(deadline))
+}
+
+/// the next date on `weekday`, starting from `today` (or tomorrow, if `exclude_today`)
+fn next_weekday(today: NaiveDate, weekday: Weekday, exclude_today: bool) -> NaiveDate {
+    let start = match exclude_today {
+        true => today + Duration::days(1),
+        false => today,
+    };
+    let days_ahead = (7 + weekday.num_days_from_monday() as i64
+        - start.weekday().num_days_from_monday() as i64)
+        % 7;
+    start + Duration::days(days_ahead)
+}
+
+impl std::str::FromStr for Recurrence {
+    type Err = anyhow::Error;
+
+    /// parses rules like `every day`, `every 2 weeks`, `every weekday`,
+    /// `every friday`, `every week on monday`, `every month on the 1st` or
+    /// `every year`, optionally followed by `when done`
+    fn from_str(input: &str) -> anyhow::Result<Self> {
+        let lowercase = input.trim().to_lowercase();
+        let (rule, when_done) = match lowercase.strip_suffix("when done") {
+            Some(rule) => (rule.trim_end(), true),
+            None => (lowercase.as_str(), false),
+        };
+        let tokens: Vec<&str> = rule.split_whitespace().collect();
+        let invalid = || anyhow!("Couldn't understand recurrence `{}`", input.trim());
+        let (every, rest) = match tokens.as_slice() {
+            ["every", n, rest @ ..] if n.parse::<u32>().is_ok_and(|n| n > 0) => {
+                (n.parse().unwrap(), rest)
+            }
+            ["every", rest @ ..] => (1, rest),
+            _ => return Err(invalid()),
+        };
+        let unit = match rest {
+            ["day" | "days"] => RecurrenceUnit::Day,
+            ["weekday" | "weekdays"] if every == 1 => RecurrenceUnit::Weekday,
+            ["week" | "weeks"] => RecurrenceUnit::Week(None),
+            ["week" | "weeks", "on", day] => {
+                RecurrenceUnit::Week(Some(day.parse().map_err(|_| invalid())?))
+            }
+            [day] if every == 1 && day.parse::<Weekday>().is_ok() => {
+                RecurrenceUnit::Week(day.parse().ok())
+            }
+            ["month" | "months"] => RecurrenceUnit::Month(None),
+            ["month" | "months", "on", "the", day] => {
+                let digits = day.trim_end_matches(|c: char| c.is_ascii_alphabetic());
+                match digits.parse::<u32>() {
+                    Ok(day @ 1..=31) => RecurrenceUnit::Month(Some(day)),
+                    _ => return Err(invalid()),
+                }
+            }
+            ["year" | "years"] => RecurrenceUnit::Year,
+            _ => return Err(invalid()),
+        };
+        Ok(Recurrence {
+            every,
+            unit,
+            when_done,
+        })
+    }
+}
+
+impl std::fmt::Display for Recurrence {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let plural = if self.every == 1 { "" } else { "s" };
+        match self.every {
+            1 => write!(f, "every ")?,
+            n => write!(f, "every {} ", n)?,
+        }
+        match self.unit {
+            RecurrenceUnit::Day => write!(f, "day{}", plural)?,
+            RecurrenceUnit::Weekday => write!(f, "weekday")?,
+            RecurrenceUnit::Week(None) => write!(f, "week{}", plural)?,
+            RecurrenceUnit::Week(Some(day)) => {
+                write!(f, "week{} on {}", plural, weekday_name(day))?
+            }
+            RecurrenceUnit::Month(None) => write!(f, "month{}", plural)?,
+            RecurrenceUnit::Month(Some(day)) => {
+                let suffix = match (day % 10, day) {
+                    (_, 11..=13) => "th",
+                    (1, _) => "st",
+                    (2, _) => "nd",
+                    (3, _) => "rd",
+                    _ => "th",
+                };
+                write!(f, "month{} on the {}{}", plural, day, suffix)?
+            }
+            RecurrenceUnit::Year => write!(f, "year{}", plural)?,
+        }
+        if self.when_done {
+            write!(f, " when done")?;
+        }
+        Ok(())
+    }
+}
+
+impl TryFrom<String> for Recurrence {
+    type Error = anyhow::Error;
+
+    fn try_from(rule: String) -> anyhow::Result<Self> {
+        rule.parse()
+    }
+}
+
+impl From<Recurrence> for String {
+    fn from(recurrence: Recurrence) -> Self {
+        recurrence.to_string()
+    }
+}
+
+impl std::str::FromStr for ListMarker {
+    type Err = anyhow::Error;
+
+    /// parses `-`, `*`, `+` or a number followed by `.` or `)`
+    fn from_str(marker: &str) -> anyhow::Result<Self> {
+        Ok(match marker {
+            "-" => ListMarker::Dash,
+            "*" => ListMarker::Star,
+            "+" => ListMarker::Plus,
+            _ => {
+                let delimiter = marker
+                    .chars()
+                    .last()
+                    .filter(|c| ['.', ')'].contains(c))
+                    .ok_or_else(|| anyhow!("Invalid list marker `{}`", marker))?;
+                // commonmark allows at most 9 digits
+                let digits = &marker[..marker.len() - 1];
+                let number = Some(digits)
+                    .filter(|d| (1..=9).contains(&d.len()) && d.chars().all(|c| c.is_ascii_digit()))
+                    .and_then(|d| d.parse().ok())
+                    .ok_or_else(|| anyhow!("Invalid list marker `{}`", marker))?;
+                ListMarker::Ordered { number, delimiter }
+            }
+        })
+    }
+}
+
+impl std::fmt::Display for ListMarker {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            ListMarker::Dash => write!(f, "-"),
+            ListMarker::Star => write!(f, "*"),
+            ListMarker::Plus => write!(f, "+"),
+            ListMarker::Ordered { number, delimiter } => write!(f, "{}{}", number, delimiter),
+        }
+    }
+}
+
+impl TryFrom<String> for ListMarker {
+    type Error = anyhow::Error;
+
+    fn try_from(marker: String) -> anyhow::Result<Self> {
+        marker.parse()
+    }
+}
+
+impl From<ListMarker> for String {
+    fn from(marker: ListMarker) -> Self {
+        marker.to_string()
+    }
+}
+
+impl Recurrence {
+    /// the first occurrence after `from`, at the same time of day
+    pub fn next(&self, from: NaiveDateTime) -> NaiveDateTime {
+        let date = from.date();
+        let next = match self.unit {
+            RecurrenceUnit::Day => date + Duration::days(self.every.into()),
+            RecurrenceUnit::Weekday => {
+                let mut next = date + Duration::days(1);
+                while next.weekday().num_days_from_monday() >= 5 {
+                    next += Duration::days(1);
+                }
+                next
+            }
+            RecurrenceUnit::Week(None) => date + Duration::weeks(self.every.into()),
+            RecurrenceUnit::Week(Some(day)) => {
+                next_weekday(date + Duration::weeks(self.every as i64 - 1), day, true)
+            }
+            RecurrenceUnit::Month(None) => date + Months::new(self.every),
+            RecurrenceUnit::Month(Some(day)) => {
+                // the day itself, or the last day of shorter months
+                let on_day = |date: NaiveDate| {
+                    let first = date.with_day(1).unwrap();
+                    let last = (first + Months::new(1) - Duration::days(1)).day();
+                    first.with_day(day.min(last)).unwrap()
+                };
+                match on_day(date) {
+                    this_month if this_month > date => this_month,
+                    _ => on_day(date + Months::new(self.every)),
+                }
+            }
+            RecurrenceUnit::Year => date + Months::new(12 * self.every),
+        };
+        NaiveDateTime::new(next, from.time())
+    }
+}
+
+fn weekday_name(day: Weekday) -> &'static str {
+    match day {
+        Weekday::Mon => "monday",
+        Weekday::Tue => "tuesday",
+        Weekday::Wed => "wednesday",
+        Weekday::Thu => "thursday",
+        Weekday::Fri => "friday",
+        Weekday::Sat => "saturday",
+        Weekday::Sun => "sunday",
+    }
+}
+
+/// parses times like `9am`, `9:30pm`, `14:00` or `9` followed by `meridiem` (`am`/`pm`)
+fn parse_time_of_day(token: &str, meridiem: Option<&str>) -> Option<NaiveTime> {
+    let (token, suffix) = match token.strip_suffix("am").or(token.strip_suffix("pm")) {
+        Some(stripped) => (stripped, Some(&token[stripped.len()..])),
+        None => (token, meridiem.filter(|m| matches!(*m, "am" | "pm"))),
+    };
+    let (hour, minute) = match token.split_once(':') {
+        Some((hour, minute)) => (hour.parse::<u32>().ok()?, minute.parse::<u32>().ok()?),
+        None if suffix.is_some() => (token.parse::<u32>().ok()?, 0),
+        None => return None,
+    };
+    let hour = match suffix {
+        Some(_) if !(1..=12).contains(&hour) => return None,
+        Some("am") => hour % 12,
+        Some(_) => hour % 12 + 12,
+        None => hour,
+    };
+    NaiveTime::from_hms_opt(hour, minute, 0)
 }
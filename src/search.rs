@@ -0,0 +1,54 @@
+use std::ops::Range;
+
+use anyhow::Result;
+use regex::{Regex, RegexBuilder};
+
+/// a search through the entries' text, as typed into the `/` and `?` prompts
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Search {
+    pub pattern: String,
+    /// whether `n` searches towards the top (started with `?`)
+    pub backward: bool,
+    /// whether `pattern` is a regular expression instead of plain text
+    pub regex: bool,
+}
+
+impl Search {
+    /// compiles the pattern. the search ignores case unless the pattern
+    /// contains uppercase characters
+    pub fn compile(&self) -> Result<Regex> {
+        let pattern = match self.regex {
+            true => self.pattern.clone(),
+            false => regex::escape(&self.pattern),
+        };
+        let regex = RegexBuilder::new(&pattern)
+            .case_insensitive(!self.pattern.chars().any(char::is_uppercase))
+            .build()?;
+        Ok(regex)
+    }
+}
+
+/// char index ranges of all non-empty matches of `regex` in `text`
+pub fn match_ranges(regex: &Regex, text: &str) -> Vec<Range<usize>> {
+    let char_idx = |byte: usize| text[..byte].chars().count();
+    regex
+        .find_iter(text)
+        .filter(|m| !m.is_empty())
+        .map(|m| char_idx(m.start())..char_idx(m.end()))
+        .collect()
+}
+
+/// the first of the sorted indices `matches` after (or before, if `backward`)
+/// `current`, wrapping around at the ends. the second value tells whether
+/// the search wrapped
+pub fn next_match(matches: &[usize], current: usize, backward: bool) -> Option<(usize, bool)> {
+    let next = match backward {
+        false => matches.iter().find(|&&i| i > current),
+        true => matches.iter().rev().find(|&&i| i < current),
+    };
+    match (next, backward) {
+        (Some(&i), _) => Some((i, false)),
+        (None, false) => matches.first().map(|&i| (i, true)),
+        (None, true) => matches.last().map(|&i| (i, true)),
+    }
+}
@@ -0,0 +1,64 @@
+use std::ops::Range;
+
+/// kind of a word marked with a sigil in an entry's text
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TagKind {
+    /// `#release`
+    Tag,
+    /// `@maria`
+    Assignee,
+}
+
+/// a `#tag` or `@assignee` found in an entry's text
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Tag {
+    pub kind: TagKind,
+    /// the name without its sigil
+    pub name: String,
+    /// char indices of the tag in the text, including the sigil
+    pub range: Range<usize>,
+}
+
+/// all `#tags` and `@assignees` in `text`. they have to start a word, so
+/// that e.g. mail addresses or `C#` aren't taken as one. tags may contain `/`
+/// and `-` and need at least one character that isn't a digit, so that issue
+/// numbers like `#42` stay plain text
+pub fn find_tags(text: &str) -> Vec<Tag> {
+    let chars: Vec<char> = text.chars().collect();
+    let mut tags = vec![];
+    let mut i = 0;
+    while i < chars.len() {
+        let kind = match chars[i] {
+            '#' => TagKind::Tag,
+            '@' => TagKind::Assignee,
+            _ => {
+                i += 1;
+                continue;
+            }
+        };
+        let starts_word = i == 0 || chars[i - 1].is_whitespace() || chars[i - 1] == '(';
+        let allowed = |c: char| c.is_alphanumeric() || c == '_' || c == '-' || c == '/';
+        let mut end = i + 1;
+        while end < chars.len() && allowed(chars[end]) {
+            end += 1;
+        }
+        // trailing separators belong to the sentence, not the tag
+        while end > i + 1 && ['-', '/'].contains(&chars[end - 1]) {
+            end -= 1;
+        }
+        let name: String = chars[i + 1..end].iter().collect();
+        let valid = match kind {
+            TagKind::Tag => name.chars().any(|c| !c.is_ascii_digit()),
+            TagKind::Assignee => !name.contains('/'),
+        };
+        if starts_word && !name.is_empty() && valid {
+            tags.push(Tag {
+                kind,
+                name,
+                range: i..end,
+            });
+        }
+        i = end.max(i + 1);
+    }
+    tags
+}
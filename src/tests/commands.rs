@@ -0,0 +1,117 @@
+use crate::*;
+
+use chrono::NaiveDateTime;
+
+/// test case: entries are appended after the last entry of the chosen section
+#[test]
+fn test_add_entry() {
+    let md = "# Chores\n\n- [ ] a\n\n## Kitchen\n\n  - [x] b\n\n## Garden\n";
+    let mut document = Document::from_md(md.to_string()).unwrap();
+    let deadline = NaiveDateTime::parse_from_str("2024-07-01 12:00", "%Y-%m-%d %H:%M").unwrap();
+
+    commands::add_entry(&mut document, " c ", Some(deadline), Some("kitchen")).unwrap();
+    commands::add_entry(&mut document, "d", None, None).unwrap();
+    assert_eq!(
+        document.to_md(),
+        "# Chores\n\n- [ ] a\n\n## Kitchen\n\n  - [x] b\n  - [ ] (2024-07-01 12:00) c\n\n## Garden\n- [ ]                    d\n"
+    );
+
+    assert!(commands::add_entry(&mut document, "e", None, Some("Bathroom")).is_err());
+    assert!(commands::add_entry(&mut document, "  ", None, None).is_err());
+}
+
+/// test case: listing filters entries and keeps their document indices
+#[test]
+fn test_list_entries() {
+    let md = "- [x] (2024-06-17 09:00) a\n- [ ] (2024-06-20 09:00) b\n  - [ ] c\n\n## Later\n\n- [ ] (2024-08-01 09:00) d\n";
+    let document = Document::from_md(md.to_string()).unwrap();
+    let now = NaiveDateTime::parse_from_str("2024-06-19 12:00", "%Y-%m-%d %H:%M").unwrap();
+    let list = |options: &commands::ListOptions, format| {
+        let listed = commands::list_entries(&document, options);
+        let mut out = termcolor::NoColor::new(vec![]);
+        let due_soon = chrono::Duration::hours(24);
+        commands::write_list(&mut out, &document, &listed, format, now, due_soon).unwrap();
+        String::from_utf8(out.into_inner()).unwrap()
+    };
+
+    let open = commands::ListOptions {
+        open: true,
+        due_before: Some(
+            NaiveDateTime::parse_from_str("2024-07-01 00:00", "%Y-%m-%d %H:%M").unwrap(),
+        ),
+        ..Default::default()
+    };
+    assert_eq!(
+        list(&open, commands::ListFormat::Plain),
+        "  2 [ ] 2024-06-20 09:00  b\n"
+    );
+
+    let later = commands::ListOptions {
+        section: Some("later".into()),
+        ..Default::default()
+    };
+    assert_eq!(
+        list(&later, commands::ListFormat::Md),
+        "- [ ] (2024-08-01 09:00) d\n"
+    );
+    let json: serde_json::Value =
+        serde_json::from_str(&list(&later, commands::ListFormat::Json)).unwrap();
+    assert_eq!(
+        json,
+        serde_json::json!([{
+            "index": 4,
+            "section": "Later",
+            "depth": 0,
+            "status": "open",
+            "deadline": "2024-08-01T09:00:00",
+            "text": "d",
+        }])
+    );
+
+    let md = "- [x] a done:2024-06-18T08:00\n- [x] b done:2024-06-16\n- [ ] c\n";
+    let completed = Document::from_md(md.to_string()).unwrap();
+    let since = commands::ListOptions {
+        completed_since: Some(
+            NaiveDateTime::parse_from_str("2024-06-17 00:00", "%Y-%m-%d %H:%M").unwrap(),
+        ),
+        ..Default::default()
+    };
+    let listed = commands::list_entries(&completed, &since);
+    assert_eq!(listed.len(), 1);
+    assert_eq!(completed.entry(&listed[0].1).unwrap().text, "a");
+
+    let all = list(&Default::default(), commands::ListFormat::Plain);
+    assert_eq!(all.lines().nth(2), Some("  3 [ ]                     c"));
+}
+
+/// test case: entries are found by index, exact text, substring or regex
+#[test]
+fn test_find_entry() {
+    let md = "- [ ] dishes\n- [ ] wash dishes\n  - [ ] dry dishes\n- [ ] Trash\n";
+    let document = Document::from_md(md.to_string()).unwrap();
+    let find = |query, regex| commands::find_entry(&document, query, regex);
+
+    assert_eq!(find("3", false).unwrap(), vec![0, 1, 0]);
+    assert!(find("5", false).is_err());
+    assert!(find("0", false).is_err());
+    // exact matches win over partial ones
+    assert_eq!(find("dishes", false).unwrap(), vec![0, 0]);
+    assert_eq!(find("trash", false).unwrap(), vec![0, 2]);
+    assert!(find("ish", false).is_err());
+    assert!(find("nothing", false).is_err());
+    assert_eq!(find("^d.y", true).unwrap(), vec![0, 1, 0]);
+    assert!(find("dishes$", true).is_err());
+
+    // numbers are indices unless an entry is named like that
+    let md = "- [ ] 2008
+- [ ] 2
+- [ ] c
+";
+    let document = Document::from_md(md.to_string()).unwrap();
+    let find = |query| commands::find_entry(&document, query, false);
+    assert_eq!(find("2008").unwrap(), vec![0, 0]);
+    assert_eq!(find("3").unwrap(), vec![0, 2]);
+    assert!(find("2").is_err());
+    assert_eq!(find("#2").unwrap(), vec![0, 1]);
+    assert!(find("#4").is_err());
+}
@@ -0,0 +1,52 @@
+use crate::*;
+
+use chrono::NaiveDateTime;
+
+fn datetime(s: &str) -> NaiveDateTime {
+    NaiveDateTime::parse_from_str(s, "%Y-%m-%d %H:%M").unwrap()
+}
+
+/// types all digits of `digits` into `editor`
+fn type_digits(editor: &mut DatetimeEditor, digits: &str) {
+    for c in digits.chars() {
+        editor.input_digit(c);
+    }
+}
+
+/// test case: typing a full deadline segment by segment
+#[test]
+fn test_type_full_deadline() {
+    let mut editor = DatetimeEditor::default();
+    editor.start(Some(datetime("2024-06-20 20:00")));
+    type_digits(&mut editor, "202501311730");
+    assert_eq!(editor.finish(), datetime("2025-01-31 17:30"));
+}
+
+/// test case: digits that would make the deadline invalid are ignored
+#[test]
+fn test_reject_invalid_digits() {
+    let mut editor = DatetimeEditor::default();
+    editor.start(Some(datetime("2024-02-10 12:00")));
+    editor.next_segment();
+    type_digits(&mut editor, "13"); // month 13
+    assert_eq!(editor.segment, DatetimeSegment::Month);
+    editor.start(Some(datetime("2024-02-10 12:00")));
+    editor.next_segment();
+    editor.next_segment();
+    type_digits(&mut editor, "30"); // february 30th
+    assert_eq!(editor.finish(), datetime("2024-02-03 12:00"));
+}
+
+/// test case: changing the month clamps the day, incrementing carries over
+#[test]
+fn test_month_change_and_increment() {
+    let mut editor = DatetimeEditor::default();
+    editor.start(Some(datetime("2023-01-31 23:30")));
+    editor.next_segment();
+    type_digits(&mut editor, "02");
+    assert_eq!(editor.value, datetime("2023-02-28 23:30"));
+    assert_eq!(editor.segment, DatetimeSegment::Day);
+    editor.next_segment();
+    editor.increment(1);
+    assert_eq!(editor.value, datetime("2023-03-01 00:30"));
+}
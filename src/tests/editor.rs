@@ -0,0 +1,60 @@
+use crate::*;
+
+use crossterm::event::{KeyCode, KeyEvent};
+
+/// feeds every char of `keys` to `editor` as a key press
+fn type_keys(editor: &mut LineEditor, keys: &str) {
+    for c in keys.chars() {
+        editor.handle_key(KeyCode::Char(c).into());
+    }
+}
+
+/// test case: inserting text and leaving insert mode
+#[test]
+fn test_insert_and_escape() {
+    let mut editor = LineEditor::default();
+    editor.start("Do dishes", usize::MAX, EditMode::Insert);
+    type_keys(&mut editor, " now");
+    editor.handle_key(KeyCode::Esc.into());
+    assert_eq!(editor.text(), "Do dishes now");
+    assert_eq!(editor.mode, EditMode::Normal);
+    assert_eq!(editor.cursor, "Do dishes now".len() - 1);
+}
+
+/// test case: word motions and operators in normal mode
+#[test]
+fn test_normal_mode_operators() {
+    let mut editor = LineEditor::default();
+    editor.start("take out the trash", 0, EditMode::Normal);
+    type_keys(&mut editor, "wdw");
+    assert_eq!(editor.text(), "take the trash");
+    type_keys(&mut editor, "cwa");
+    editor.handle_key(KeyCode::Esc.into());
+    assert_eq!(editor.text(), "take a trash");
+    type_keys(&mut editor, "0dfe");
+    assert_eq!(editor.text(), " a trash");
+    type_keys(&mut editor, "u");
+    assert_eq!(editor.text(), "take a trash");
+    type_keys(&mut editor, "$bD");
+    assert_eq!(editor.text(), "take a ");
+}
+
+/// test case: visual selection, yanking and pasting
+#[test]
+fn test_visual_yank_and_paste() {
+    let mut editor = LineEditor::default();
+    editor.start("foo bar", 0, EditMode::Normal);
+    type_keys(&mut editor, "viwy$p");
+    assert_eq!(editor.text(), "foo barfoo");
+    type_keys(&mut editor, "0vlx");
+    assert_eq!(editor.text(), "o barfoo");
+}
+
+/// test case: deleting the previous word in insert mode
+#[test]
+fn test_insert_delete_word() {
+    let mut editor = LineEditor::default();
+    editor.start("call mom tomorrow", usize::MAX, EditMode::Insert);
+    editor.handle_key(KeyEvent::new(KeyCode::Char('w'), KeyModifiers::CONTROL));
+    assert_eq!(editor.text(), "call mom ");
+}
@@ -0,0 +1,61 @@
+use crate::*;
+
+fn snapshot(text: &str, active_entry_idx: usize) -> Snapshot {
+    Snapshot {
+        document: Document::from_md(text.to_string()).unwrap(),
+        active_entry_idx,
+    }
+}
+
+#[test]
+fn undo_redo() {
+    let mut history = History::default();
+    let a = snapshot("- [ ] a\n", 0);
+    let b = snapshot("- [x] a\n", 0);
+
+    history.begin(a.clone());
+    history.end(&b.document);
+
+    assert_eq!(history.redo(b.clone()), None);
+    assert_eq!(history.undo(b.clone()), Some(a.clone()));
+    assert_eq!(history.undo(a.clone()), None);
+    assert_eq!(history.redo(a.clone()), Some(b.clone()));
+}
+
+#[test]
+fn nested_transactions_are_one_step() {
+    let mut history = History::default();
+    let a = snapshot("- [ ] a\n", 0);
+    let b = snapshot("- [ ] a\n- [ ] b\n", 1);
+    let c = snapshot("- [ ] a\n- [ ] b (2024-06-20 16:00)\n", 1);
+
+    history.begin(a.clone());
+    history.begin(b.clone());
+    history.end(&c.document);
+    assert!(history.in_transaction());
+    history.end(&c.document);
+
+    assert_eq!(history.undo(c.clone()), Some(a.clone()));
+    assert_eq!(history.undo(a), None);
+}
+
+#[test]
+fn unchanged_and_bounded() {
+    let mut history = History::new(2);
+    let docs: Vec<_> = (0..4)
+        .map(|i| snapshot(&format!("- [ ] {i}\n"), 0))
+        .collect();
+
+    // no change, no undo step
+    history.begin(docs[0].clone());
+    history.end(&docs[0].document);
+    assert_eq!(history.undo(docs[0].clone()), None);
+
+    for pair in docs.windows(2) {
+        history.begin(pair[0].clone());
+        history.end(&pair[1].document);
+    }
+    assert_eq!(history.undo(docs[3].clone()), Some(docs[2].clone()));
+    assert_eq!(history.undo(docs[2].clone()), Some(docs[1].clone()));
+    assert_eq!(history.undo(docs[1].clone()), None);
+}
@@ -0,0 +1,55 @@
+use crate::*;
+
+use chrono::NaiveDateTime;
+
+fn datetime(s: &str) -> NaiveDateTime {
+    NaiveDateTime::parse_from_str(s, "%Y-%m-%d %H:%M").unwrap()
+}
+
+/// test case: entries with deadlines become vtodos with stable uids
+#[test]
+fn test_ics_export() {
+    let md = "# Chores\n\n- [x] (2024-06-20 16:00) Get groceries\n- [ ] no deadline\n- [ ] (2024-06-20 20:00) Dishes, pots; pans\n    - [ ] (2024-06-20 19:00) Soak pots\n- [ ] (2024-06-21 20:00) Dishes, pots; pans\n";
+    let document = Document::from_md(md.to_string()).unwrap();
+    let ics = ical::to_ics(&document, datetime("2024-06-19 12:00"));
+
+    assert!(ics.starts_with("BEGIN:VCALENDAR\r\nVERSION:2.0\r\n"));
+    assert!(ics.ends_with("END:VCALENDAR\r\n"));
+    assert_eq!(ics.matches("BEGIN:VTODO").count(), 4);
+    assert!(ics.contains("X-WR-CALNAME:Chores\r\n"));
+    assert!(ics.contains(
+        "SUMMARY:Dishes\\, pots\\; pans\r\nDUE:20240620T200000\r\nSTATUS:NEEDS-ACTION\r\n"
+    ));
+    assert!(ics.contains("DUE:20240620T160000\r\nSTATUS:COMPLETED\r\n"));
+
+    let uids: Vec<&str> = ics.lines().filter(|l| l.starts_with("UID:")).collect();
+    assert_eq!(uids.len(), 4);
+    assert_ne!(uids[1], uids[3]);
+    // uids don't depend on the position or other entries
+    let moved =
+        Document::from_md("# Chores\n- [ ] (2024-06-22 20:00) Get groceries\n".into()).unwrap();
+    assert!(ical::to_ics(&moved, datetime("2024-06-19 12:00")).contains(uids[0]));
+
+    // exporting and importing keeps entries with deadlines
+    let imported = ical::from_ics(&ics).unwrap();
+    assert_eq!(imported.title, Some("Chores".to_string()));
+    assert_eq!(imported.entry_paths(false).len(), 4);
+    assert_eq!(imported.entry(&[0, 3]).unwrap().text, "Dishes, pots; pans");
+}
+
+/// test case: vtodos written by other calendar clients
+#[test]
+fn test_ics_import() {
+    let ics = include_str!("data/calendar.ics");
+    let document = ical::from_ics(ics).unwrap();
+    assert_eq!(
+        document.to_md(),
+        "# Chores\n\n- [ ] (2024-06-20 20:00) Do the dishes, then take out the trash; and wipe the table with a very long summary\n- [x] (2024-06-21 23:59) Get groceries\n"
+    );
+    assert!(ical::from_ics("- [ ] not a calendar").is_err());
+
+    // todos without a summary can't be written as an entry
+    let ics = "BEGIN:VCALENDAR\r\nBEGIN:VTODO\r\nDUE:20240620T200000\r\nEND:VTODO\r\nBEGIN:VTODO\r\nSUMMARY: \r\nEND:VTODO\r\nBEGIN:VTODO\r\nSUMMARY:a\r\nEND:VTODO\r\nEND:VCALENDAR\r\n";
+    let document = ical::from_ics(ics).unwrap();
+    assert_eq!(document.to_md(), "- [ ]                    a\n");
+}
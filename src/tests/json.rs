@@ -0,0 +1,64 @@
+use crate::*;
+
+/// test case: exporting and importing gives back identical markdown
+#[test]
+fn test_json_round_trip() {
+    let md = "# Chores\r\n\r\nSome prose.\r\n\r\n## Kitchen\r\n\r\n-  [x]    oddly spaced\r\n- [ ] (2024-06-20 20:00) Do the dishes\r\n    - [ ] subtask\r\n      a note\r\n\r\n```\r\n- [ ] code\r\n```";
+    let document = Document::from_md(md.to_string()).unwrap();
+    let exported = json::to_json(&document).unwrap();
+    assert!(exported.contains("\"deadline\": \"2024-06-20T20:00:00\""));
+
+    // the content of entries is separate from how they are written
+    let json: serde_json::Value = serde_json::from_str(&exported).unwrap();
+    let entry = &json["sections"][1]["entries"][1];
+    assert_eq!(entry["text"], "Do the dishes");
+    assert_eq!(entry["children"][0]["notes"], serde_json::json!(["a note"]));
+    assert_eq!(
+        entry["format"]["source"],
+        "- [ ] (2024-06-20 20:00) Do the dishes"
+    );
+    assert!(entry.get("source").is_none());
+
+    let imported = json::from_json(&exported).unwrap();
+    assert_eq!(imported.to_md(), md);
+}
+
+/// test case: json written by other tools only needs the relevant fields
+#[test]
+fn test_json_minimal_import() {
+    let json = r#"{
+        "title": "Chores",
+        "sections": [
+            {"entries": [{"text": "a", "status": "done"}]},
+            {"name": "Kitchen", "level": 2, "entries": [
+                {"text": "b", "deadline": "2024-06-20T20:00:00", "notes": ["see #shop"], "children": [
+                    {"text": "c"}
+                ]}
+            ]}
+        ]
+    }"#;
+    let document = json::from_json(json).unwrap();
+    assert_eq!(
+        document.to_md(),
+        "# Chores\n\n- [x]                    a\n## Kitchen\n- [ ] (2024-06-20 20:00) b\n  see #shop\n  - [ ]                    c\n"
+    );
+    assert!(json::from_json("\"chores\"").is_err());
+}
+
+/// test case: priorities that markdown can't represent are rejected
+#[test]
+fn test_json_invalid_priority() {
+    let json = |priority: &str| {
+        format!(
+            r#"{{"sections": [{{"entries": [{{"text": "fix prod", "priority": "{priority}"}}]}}]}}"#
+        )
+    };
+    let document = json::from_json(&json("Z")).unwrap();
+    assert_eq!(document.to_md(), "- [ ]                    [#Z] fix prod\n");
+    let reread = Document::from_md(document.to_md()).unwrap();
+    assert_eq!(reread.entry(&[0, 0]).unwrap().priority, Some('Z'));
+
+    assert!(json::from_json(&json("a")).is_err());
+    assert!(json::from_json(&json("Ā")).is_err());
+    assert!(json::from_json(&json("1")).is_err());
+}
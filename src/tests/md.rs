@@ -8,9 +8,10 @@ fn test_valid_fake_date_not_done_dateless() {
     assert_eq!(
         entry.unwrap(),
         Entry {
-            done: false,
+            status: Status::Open,
             deadline: None,
             text: "(2024-06-what) this should work, kind of".to_string(),
+            ..Default::default()
         }
     );
 }
@@ -23,12 +24,13 @@ fn test_valid_weirdly_spaced_done_dated() {
     assert_eq!(
         entry.unwrap(),
         Entry {
-            done: true,
+            status: Status::Done,
             deadline: Some(
                 chrono::NaiveDateTime::parse_from_str("(2024-06-20 20:30)", "(%Y-%m-%d %H:%M)")
                     .unwrap()
             ),
             text: "weirdly spaced but ok".to_string(),
+            ..Default::default()
         }
     );
 }
@@ -40,3 +42,452 @@ fn test_invalid_bad_checkbox_fill() {
     dbg!(&entry);
     assert!(entry.is_err());
 }
+
+/// test case: extended checkbox states are parsed and written back
+#[test]
+fn test_checkbox_states() {
+    let status = |md: &str| Entry::from_md(md.to_string()).unwrap().status;
+    assert_eq!(status("- [X] shouting"), Status::Done);
+    assert_eq!(status("- [-] cancelled"), Status::Cancelled);
+    assert_eq!(status("- [/] in progress"), Status::InProgress);
+    assert_eq!(status("- [>] deferred"), Status::Deferred);
+
+    let md = "- [/] Paint the fence\n- [X] Buy paint\n";
+    let mut document = Document::from_md(md.to_string()).unwrap();
+    assert_eq!(document.to_md(), md);
+    let cycle = [Status::Open, Status::InProgress, Status::Done];
+    let entry = document.entry_mut(&[0, 0]).unwrap();
+    entry.status = entry.status.next_in(&cycle);
+    assert_eq!(entry.status, Status::Done);
+    assert_eq!(entry.status.next_in(&cycle), Status::Open);
+    assert_eq!(Status::Deferred.next_in(&cycle), Status::Open);
+    assert!(document.to_md().starts_with("- [x]"));
+}
+
+/// test case: priority markers are parsed and written back in the same style
+#[test]
+fn test_priority_markers() {
+    let entry = |md: &str| Entry::from_md(md.to_string()).unwrap();
+    for (md, priority, style) in [
+        ("- [ ] !!! Fix prod", 'A', PriorityStyle::Bangs),
+        ("- [ ] ! Fix prod", 'C', PriorityStyle::Bangs),
+        ("- [ ] (B) Fix prod", 'B', PriorityStyle::Letter),
+        ("- [ ] [#A] Fix prod", 'A', PriorityStyle::Org),
+    ] {
+        let parsed = entry(md);
+        assert_eq!(parsed.text, "Fix prod");
+        assert_eq!(parsed.priority, Some(priority));
+        assert_eq!(parsed.priority_style, style);
+        assert_eq!(parsed.to_md().replace(' ', ""), md.replace(' ', ""));
+    }
+    assert_eq!(entry("- [ ] (Bob) said hi").priority, None);
+    let prose = entry("- [ ] (I) Introduction chapter");
+    assert_eq!(prose.priority, None);
+    assert_eq!(prose.text, "(I) Introduction chapter");
+    assert_eq!(entry("- [ ] [#D] Fix prod").priority, Some('D'));
+    assert_eq!(entry("- [ ] !!!").text, "!!!");
+
+    let mut bangs = entry("- [ ] !! Fix prod");
+    bangs.priority = Some('D');
+    assert!(bangs.to_md().ends_with("[#D] Fix prod"));
+}
+
+/// test case: obsidian tasks' emoji metadata is parsed and written back in the same style
+#[test]
+fn test_obsidian_emoji_metadata() {
+    let md = "- [x] Water plants ⏫ 🔁 every week 🛫 2024-06-17 ⏳ 2024-06-18 📅 2024-06-20 ✅ 2024-06-21";
+    let entry = Entry::from_md(md.to_string()).unwrap();
+    let date = |s| chrono::NaiveDate::parse_from_str(s, "%Y-%m-%d").unwrap();
+    assert_eq!(entry.text, "Water plants");
+    assert_eq!(entry.priority, Some('B'));
+    assert_eq!(entry.recurrence, "every week".parse().ok());
+    assert_eq!(entry.start, Some(date("2024-06-17")));
+    assert_eq!(entry.scheduled, Some(date("2024-06-18")));
+    assert_eq!(entry.deadline, date("2024-06-20").and_hms_opt(23, 59, 0));
+    assert_eq!(entry.completed, date("2024-06-21").and_hms_opt(0, 0, 0));
+    assert_eq!(entry.dialect, Dialect::Obsidian);
+    assert_eq!(entry.to_md(), md);
+
+    // malformed metadata stays part of the text
+    let entry = Entry::from_md("- [ ] Meet at 📅 noon".to_string()).unwrap();
+    assert_eq!(entry.text, "Meet at 📅 noon");
+    assert_eq!(entry.dialect, Dialect::Todue);
+
+    // only a `📅` deadline makes an entry obsidian, so deadlines keep their time
+    let mut entry = Entry::from_md("- [ ] Water plants 🔁 every week".to_string()).unwrap();
+    assert_eq!(entry.dialect, Dialect::Todue);
+    entry.deadline = date("2024-06-20").and_hms_opt(16, 0, 0);
+    assert_eq!(
+        entry.to_md(),
+        "- [ ] (2024-06-20 16:00) Water plants 🔁 every week"
+    );
+
+    // unless the other entries of the document are
+    let md = "- [ ] Call mom 📅 2024-06-21\n- [ ] Water plants 🔁 every week\n";
+    let mut document = Document::from_md(md.to_string()).unwrap();
+    assert_eq!(document.entry(&[0, 1]).unwrap().dialect, Dialect::Obsidian);
+    assert_eq!(document.to_md(), md);
+    document.entry_mut(&[0, 1]).unwrap().status = Status::Done;
+    assert_eq!(
+        document.to_md(),
+        "- [ ] Call mom 📅 2024-06-21\n- [x] Water plants 🔁 every week\n"
+    );
+}
+
+/// test case: the dialect only changes how the deadline is written
+#[test]
+fn test_dialect_conversion() {
+    let md = "- [ ] (2024-06-20 16:00) Water plants 🔼\n- [ ] Call mom 📅 2024-06-21\n";
+    let mut document = Document::from_md(md.to_string()).unwrap();
+    document.set_dialect(Dialect::Obsidian);
+    assert_eq!(
+        document.to_md(),
+        "- [ ] Water plants 🔼 📅 2024-06-20\n- [ ] Call mom 📅 2024-06-21\n"
+    );
+    document.set_dialect(Dialect::Todue);
+    assert_eq!(
+        document.to_md(),
+        "- [ ] (2024-06-20 16:00) Water plants 🔼\n- [ ] (2024-06-21 23:59) Call mom\n"
+    );
+}
+
+/// test case: non-entry markdown survives a round-trip unchanged
+#[test]
+fn test_document_round_trip_lossless() {
+    let md = "# Chores\n\nSome prose with a [link](https://example.com).\n\n## Kitchen\n\n```\n- [ ] not an entry, just code\n```\n-  [x]    oddly spaced entry\n- [ ] (2024-06-20 20:00) Do the dishes\n\ntrailing text";
+    let document = Document::from_md(md.to_string()).unwrap();
+    assert_eq!(document.title, Some("Chores".to_string()));
+    assert_eq!(document.entry_paths(false).len(), 2);
+    assert_eq!(document.to_md(), md);
+}
+
+/// test case: only modified entries are rewritten
+#[test]
+fn test_document_rewrites_only_changed_entries() {
+    let md = "# Chores\r\n\r\n-  [x]    first\r\n-  [ ]    second\r\n";
+    let mut document = Document::from_md(md.to_string()).unwrap();
+    document.entry_mut(&[0, 3]).unwrap().status = Status::Done;
+    assert_eq!(
+        document.to_md(),
+        "# Chores\r\n\r\n-  [x]    first\r\n- [x]                    second\r\n"
+    );
+}
+
+/// test case: indented entries become subtasks and keep their indentation
+#[test]
+fn test_document_subtask_tree() {
+    let md =
+        "- [ ] parent\n    - [ ] child\n\n        - [x] grandchild\n    some note\n- [ ] sibling\n";
+    let mut document = Document::from_md(md.to_string()).unwrap();
+    assert_eq!(document.indent_width, 4);
+    assert_eq!(
+        document.entry_paths(false),
+        vec![vec![0, 0], vec![0, 0, 0], vec![0, 0, 0, 1], vec![0, 1]]
+    );
+    assert_eq!(document.entry(&[0, 0, 0, 1]).unwrap().text, "grandchild");
+    assert_eq!(document.to_md(), md);
+
+    document.entry_mut(&[0, 0, 0, 1]).unwrap().status = Status::Open;
+    assert!(document
+        .to_md()
+        .contains("\n        - [ ]                    grandchild\n"));
+}
+
+/// test case: moving an entry carries its subtasks along
+#[test]
+fn test_document_move_entry_with_subtasks() {
+    let md = "- [ ] a\n  - [ ] a1\n- [ ] b\n";
+    let mut document = Document::from_md(md.to_string()).unwrap();
+    let new_path = document.move_entry(&[0, 0], MoveDirection::Down);
+    assert_eq!(new_path, Some(vec![0, 1]));
+    assert_eq!(document.to_md(), "- [ ] b\n- [ ] a\n  - [ ] a1\n");
+}
+
+/// test case: headings start sections owning the entries below them
+#[test]
+fn test_document_sections() {
+    let md = "# Todo\n\n## Work\n\n- [ ] a\n- [ ] b\n\n## Home\n\n- [ ] c\n\n### Garden\n";
+    let document = Document::from_md(md.to_string()).unwrap();
+    let names: Vec<_> = document.sections.iter().map(|s| s.name.as_str()).collect();
+    assert_eq!(names, vec!["", "Work", "Home", "Garden"]);
+    assert_eq!(document.sections[3].level, 3);
+    assert_eq!(document.entry(&[2, 1]).unwrap().text, "c");
+    assert_eq!(document.to_md(), md);
+}
+
+/// test case: moving entries past the edge of a section moves them into the next one
+#[test]
+fn test_document_move_entry_across_sections() {
+    let md = "## Work\n\n- [ ] a\n- [ ] b\n\n## Home\n\n- [ ] c\n\n## Later\n\n";
+    let mut document = Document::from_md(md.to_string()).unwrap();
+
+    let path = document.move_entry(&[1, 2], MoveDirection::Down).unwrap();
+    assert_eq!(path, vec![2, 1]);
+    assert_eq!(document.entry(&path).unwrap().text, "b");
+
+    let path = document.move_entry(&[2, 2], MoveDirection::Down).unwrap();
+    assert_eq!(path, vec![3, 1]);
+    assert_eq!(
+        document.to_md(),
+        "## Work\n\n- [ ] a\n\n## Home\n\n- [ ] b\n\n## Later\n\n- [ ] c\n"
+    );
+}
+
+/// test case: natural language deadlines resolve relative to now
+#[test]
+fn test_parse_natural_deadlines() {
+    let parse = |s| chrono::NaiveDateTime::parse_from_str(s, "%Y-%m-%d %H:%M").unwrap();
+    // a thursday
+    let now = parse("2024-06-20 10:17");
+    let cases = [
+        ("(2024-07-01 12:00)", "2024-07-01 12:00"),
+        ("2024-07-01 12:00", "2024-07-01 12:00"),
+        ("tomorrow 9am", "2024-06-21 09:00"),
+        ("fri", "2024-06-21 23:59"),
+        ("thursday", "2024-06-20 23:59"),
+        ("next thu", "2024-06-27 23:59"),
+        ("in 3d", "2024-06-23 10:17"),
+        ("in 2 hours", "2024-06-20 12:17"),
+        ("next monday 14:00", "2024-06-24 14:00"),
+        ("eod", "2024-06-20 23:59"),
+        ("9:30 pm", "2024-06-20 21:30"),
+        ("8am", "2024-06-21 08:00"),
+        ("2024-12-24 at noon", "2024-12-24 12:00"),
+    ];
+    for (input, expected) in cases {
+        assert_eq!(
+            parse_deadline(input, now).unwrap(),
+            parse(expected),
+            "{input}"
+        );
+    }
+    assert!(parse_deadline("someday", now).is_err());
+    assert!(parse_deadline("13pm", now).is_err());
+
+    let start = |s| parse_datetime(s, now, chrono::NaiveTime::MIN).unwrap();
+    assert_eq!(start("2024-07-01"), parse("2024-07-01 00:00"));
+    assert_eq!(start("2024-07-01 9am"), parse("2024-07-01 09:00"));
+}
+
+/// test case: recurrence rules and their next occurrence
+#[test]
+fn test_recurrence() {
+    let at = |s: &str| chrono::NaiveDateTime::parse_from_str(s, "%Y-%m-%d %H:%M").unwrap();
+    let next = |rule: &str, from: &str| {
+        let recurrence: Recurrence = rule.parse().unwrap();
+        recurrence
+            .next(at(from))
+            .format("%Y-%m-%d %H:%M")
+            .to_string()
+    };
+    // 2024-06-21 is a friday
+    assert_eq!(next("every day", "2024-06-21 09:00"), "2024-06-22 09:00");
+    assert_eq!(
+        next("every 2 weeks", "2024-06-21 09:00"),
+        "2024-07-05 09:00"
+    );
+    assert_eq!(
+        next("every weekday", "2024-06-21 09:00"),
+        "2024-06-24 09:00"
+    );
+    assert_eq!(next("Every Monday", "2024-06-21 09:00"), "2024-06-24 09:00");
+    assert_eq!(
+        next("every month on the 1st", "2024-06-21 09:00"),
+        "2024-07-01 09:00"
+    );
+    assert_eq!(
+        next("every month on the 31st", "2024-06-21 09:00"),
+        "2024-06-30 09:00"
+    );
+    assert_eq!(next("every month", "2024-01-31 09:00"), "2024-02-29 09:00");
+    assert_eq!(
+        next("every year when done", "2024-06-21 09:00"),
+        "2025-06-21 09:00"
+    );
+
+    for rule in [
+        "every 3 days",
+        "every week on friday",
+        "every month on the 22nd when done",
+    ] {
+        assert_eq!(rule.parse::<Recurrence>().unwrap().to_string(), rule);
+    }
+    assert!("every 0 days".parse::<Recurrence>().is_err());
+    assert!("every blue moon".parse::<Recurrence>().is_err());
+}
+
+/// test case: completing a recurring entry creates its next occurrence
+#[test]
+fn test_respawn_recurring() {
+    let md = "# Chores\n\n- [x] Water plants 🔁 every week ⏳ 2024-06-19 📅 2024-06-21\n- [ ] Call mom\n";
+    let now = chrono::NaiveDateTime::parse_from_str("2024-06-22 10:00", "%Y-%m-%d %H:%M").unwrap();
+
+    let mut document = Document::from_md(md.to_string()).unwrap();
+    assert_eq!(
+        document.respawn_recurring(&[0, 2], false, now),
+        Some(vec![0, 2])
+    );
+    assert_eq!(
+        document.to_md(),
+        "# Chores\n\n- [ ] Water plants 🔁 every week ⏳ 2024-06-26 📅 2024-06-28\n- [x] Water plants 🔁 every week ⏳ 2024-06-19 📅 2024-06-21\n- [ ] Call mom\n"
+    );
+
+    let mut document = Document::from_md(md.to_string()).unwrap();
+    document.respawn_recurring(&[0, 2], true, now);
+    assert_eq!(
+        document.to_md(),
+        "# Chores\n\n- [ ] Water plants 🔁 every week ⏳ 2024-06-26 📅 2024-06-28\n- [ ] Call mom\n\n## Archive\n\n- [x] Water plants 🔁 every week ⏳ 2024-06-19 📅 2024-06-21\n"
+    );
+    // new entries still go to the last section before the archive
+    assert_eq!(document.default_section(), 0);
+
+    // the archive isn't nested below a trailing subsection
+    let md = "# Chores\n## Garden\n- [x] Water plants 🔁 every week\n### Tools\n- [ ] Oil shears\n";
+    let mut document = Document::from_md(md.to_string()).unwrap();
+    document.respawn_recurring(&[1, 0], true, now);
+    let archive = document.section_idx("Archive").unwrap();
+    assert_eq!(document.sections[archive].level, 2);
+    assert_eq!(document.default_section(), archive - 1);
+}
+
+/// test case: creation and completion timestamps are trailing markers
+#[test]
+fn test_timestamps() {
+    let datetime = |s| chrono::NaiveDateTime::parse_from_str(s, "%Y-%m-%d %H:%M").unwrap();
+    let md = "- [ ] Call mom created:2024-06-01 done:oops created:2024-06-02T09:30";
+    let mut entry = Entry::from_md(md.to_string()).unwrap();
+    assert_eq!(entry.text, "Call mom created:2024-06-01 done:oops");
+    assert_eq!(entry.created, Some(datetime("2024-06-02 09:30")));
+
+    entry.text = "Call mom".into();
+    entry.set_status(Status::Done, datetime("2024-06-21 10:00"));
+    assert_eq!(entry.completed, Some(datetime("2024-06-21 10:00")));
+    assert!(entry
+        .to_md()
+        .ends_with("Call mom created:2024-06-02T09:30 done:2024-06-21T10:00"));
+    // completing again keeps the first stamp
+    entry.set_status(Status::Done, datetime("2024-06-22 10:00"));
+    assert_eq!(entry.completed, Some(datetime("2024-06-21 10:00")));
+
+    entry.dialect = Dialect::Obsidian;
+    assert!(entry
+        .to_md()
+        .ends_with("Call mom ➕ 2024-06-02 ✅ 2024-06-21"));
+
+    entry.set_status(Status::Open, datetime("2024-06-23 10:00"));
+    assert_eq!(entry.completed, None);
+}
+
+/// test case: indented lines below an entry are its notes
+#[test]
+fn test_entry_notes() {
+    let md = "- [ ] Release\n  See the [checklist](https://example.com).\n\n  - bump version\n    - in Cargo.toml\n  - [ ] Tag\n- [ ] Other\n";
+    let mut document = Document::from_md(md.to_string()).unwrap();
+    assert_eq!(document.to_md(), md);
+
+    let entry = document.entry(&[0, 0]).unwrap();
+    assert!(entry.has_notes());
+    assert_eq!(
+        entry.notes(),
+        [
+            "See the [checklist](https://example.com).",
+            "",
+            "- bump version",
+            "  - in Cargo.toml"
+        ]
+    );
+    assert!(!document.entry(&[0, 1]).unwrap().has_notes());
+
+    document.set_notes(&[0, 0], &["Only this".into()]).unwrap();
+    document.set_notes(&[0, 1], &["New".into(), "".into(), "  - nested".into()]);
+    assert_eq!(
+        document.to_md(),
+        "- [ ] Release\n  Only this\n  - [ ] Tag\n- [ ] Other\n  New\n\n    - nested\n"
+    );
+    document.set_notes(&[0, 0], &[]).unwrap();
+    assert!(!document.entry(&[0, 0]).unwrap().has_notes());
+    assert!(document.entry(&[0, 0]).unwrap().has_subtasks());
+
+    // notes stay where they are, blank lines between subtasks aren't notes
+    let md = "- [ ] parent\n    - [ ] sub1\n\n    - [ ] sub2\n    trailing note\n";
+    let mut document = Document::from_md(md.to_string()).unwrap();
+    let notes = document.entry(&[0, 0]).unwrap().notes();
+    assert_eq!(notes, ["trailing note"]);
+    document.set_notes(&[0, 0], &notes).unwrap();
+    assert_eq!(document.to_md(), md);
+    document.set_notes(&[0, 0], &["changed".into(), "added".into()]);
+    assert_eq!(
+        document.to_md(),
+        "- [ ] parent\n    - [ ] sub1\n\n    - [ ] sub2\n    changed\n    added\n"
+    );
+}
+
+/// test case: all list markers are kept, ordered lists stay numbered after moves
+#[test]
+fn test_list_markers() {
+    let marker = |md: &str| Entry::from_md(md.to_string()).unwrap().marker;
+    assert_eq!(marker("* [ ] a"), ListMarker::Star);
+    assert_eq!(marker("+ [x] a"), ListMarker::Plus);
+    assert_eq!(
+        marker("12) [ ] a"),
+        ListMarker::Ordered {
+            number: 12,
+            delimiter: ')'
+        }
+    );
+    assert!(Entry::from_md("1234567890. [ ] a".to_string()).is_err());
+    assert!(Entry::from_md("1: [ ] a".to_string()).is_err());
+
+    // markers only count at the start of a line, followed by a space
+    let prose = [
+        "**Note**: [x] done",
+        "foo * [ ] bar",
+        "Use `- [ ]` for tasks",
+        "12- [ ] weird",
+        "-[ ]x",
+        "1.[x]y",
+    ];
+    for line in prose {
+        assert!(Entry::from_md(line.to_string()).is_err(), "{line}");
+    }
+    let document = Document::from_md(prose.join("\n") + "\n").unwrap();
+    assert!(document.sections[0]
+        .blocks
+        .iter()
+        .all(|block| matches!(block, Block::Text(_))));
+
+    let md = "* [ ] a\n+ [ ] b\n\n1. [ ] c\n2. [x] d\n\n3. [ ] e\n   - [ ] f\n";
+    let mut document = Document::from_md(md.to_string()).unwrap();
+    assert_eq!(document.to_md(), md);
+
+    // renumbered entries keep their line, new ones are padded where the
+    // deadline would be
+    let pad = " ".repeat("(YYYY-mm-dd HH:MM) ".len());
+    document.move_entry(&[0, 6], MoveDirection::Up);
+    assert_eq!(
+        document.to_md(),
+        "* [ ] a\n+ [ ] b\n\n1. [ ] c\n2. [ ] e\n   - [ ] f\n\n3. [x] d\n"
+    );
+    // subtasks and notes follow the marker across the 9/10 boundary
+    let items: String = (1..=9).map(|n| format!("{n}. [ ] {n}\n")).collect();
+    let md = items + "10. [ ] ten\n    - [ ] sub\n    note\n";
+    let mut crossed = Document::from_md(md.clone()).unwrap();
+    let first = crossed.remove_entry(&[0, 0]).unwrap();
+    assert!(crossed
+        .to_md()
+        .ends_with("8. [ ] 9\n9. [ ] ten\n   - [ ] sub\n   note\n"));
+    crossed.insert_entry(&[0, 0], first);
+    assert_eq!(crossed.to_md(), md);
+
+    let mut swapped = Document::from_md("1. [ ] a\n2. [ ] b\n".to_string()).unwrap();
+    swapped.move_entry(&[0, 0], MoveDirection::Down);
+    assert_eq!(swapped.to_md(), "1. [ ] b\n2. [ ] a\n");
+
+    document.remove_entry(&[0, 3]);
+    let path = document.push_entry(0, Entry::default()).unwrap();
+    assert_eq!(path, vec![0, 6]);
+    assert_eq!(
+        document.to_md(),
+        format!("* [ ] a\n+ [ ] b\n\n1. [ ] e\n   - [ ] f\n\n2. [x] d\n3. [ ] {pad}\n")
+    );
+}
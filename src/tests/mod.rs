@@ -1,2 +1,22 @@
 #[cfg(test)]
+mod commands;
+#[cfg(test)]
+mod datetime;
+#[cfg(test)]
+mod editor;
+#[cfg(test)]
+mod history;
+#[cfg(test)]
+mod ical;
+#[cfg(test)]
+mod json;
+#[cfg(test)]
 mod md;
+#[cfg(test)]
+mod search;
+#[cfg(test)]
+mod tags;
+#[cfg(test)]
+mod todotxt;
+#[cfg(test)]
+mod view;
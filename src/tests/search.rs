@@ -0,0 +1,42 @@
+use crate::*;
+
+#[test]
+fn plain_and_regex_patterns() {
+    let search = |pattern: &str, regex| Search {
+        pattern: pattern.into(),
+        regex,
+        ..Default::default()
+    };
+
+    // plain patterns are taken literally, and ignore case unless they contain uppercase
+    let re = search("a.b", false).compile().unwrap();
+    assert!(re.is_match("xA.B"));
+    assert!(!re.is_match("axb"));
+    let re = search("Dishes", false).compile().unwrap();
+    assert!(!re.is_match("do the dishes"));
+
+    let re = search("^do .*s$", true).compile().unwrap();
+    assert!(re.is_match("Do the dishes"));
+    assert!(search("(", true).compile().is_err());
+}
+
+#[test]
+fn match_ranges_are_char_indices() {
+    let re = Search {
+        pattern: "de".into(),
+        ..Default::default()
+    }
+    .compile()
+    .unwrap();
+    assert_eq!(match_ranges(&re, "ändere die Decke"), vec![2..4, 11..13]);
+}
+
+#[test]
+fn next_match_wraps() {
+    let matches = [1, 4, 7];
+    assert_eq!(next_match(&matches, 1, false), Some((4, false)));
+    assert_eq!(next_match(&matches, 7, false), Some((1, true)));
+    assert_eq!(next_match(&matches, 4, true), Some((1, false)));
+    assert_eq!(next_match(&matches, 0, true), Some((7, true)));
+    assert_eq!(next_match(&[], 0, false), None);
+}
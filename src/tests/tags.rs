@@ -0,0 +1,37 @@
+use crate::*;
+
+/// test case: tags and assignees have to start a word
+#[test]
+fn test_find_tags() {
+    let text = "Ship #release/v2 with @maria-k, see #42 and mail bob@example.com about C#";
+    let tags = find_tags(text);
+    let names: Vec<_> = tags.iter().map(|t| (t.kind, t.name.as_str())).collect();
+    assert_eq!(
+        names,
+        [(TagKind::Tag, "release/v2"), (TagKind::Assignee, "maria-k")]
+    );
+    let chars: Vec<char> = text.chars().collect();
+    let tag: String = chars[tags[0].range.clone()].iter().collect();
+    assert_eq!(tag, "#release/v2");
+}
+
+/// test case: tags are parsed into fields, the text keeps them
+#[test]
+fn test_entry_tags() {
+    let md = "- [ ] Prepare #release notes for @maria and @tom #release";
+    let entry = Entry::from_md(md.to_string()).unwrap();
+    assert_eq!(entry.tags, ["release"]);
+    assert_eq!(entry.assignees, ["maria", "tom"]);
+    assert!(entry.to_md().ends_with(&md["- [ ] ".len()..]));
+
+    let now = chrono::Local::now().naive_local();
+    assert!(Filter::Tags(vec!["@TOM".into()]).matches(&entry, now));
+    assert!(Filter::Tags(vec!["#docs".into(), "#release".into()]).matches(&entry, now));
+    assert!(!Filter::Tags(vec!["#docs".into()]).matches(&entry, now));
+
+    let document = Document::from_md(format!("{md}\n- [ ] Write #docs\n")).unwrap();
+    assert_eq!(
+        tag_labels(&document),
+        ["#docs", "#release", "@maria", "@tom"]
+    );
+}
@@ -0,0 +1,80 @@
+use crate::*;
+
+use chrono::{NaiveDate, NaiveDateTime};
+
+/// todo.txt dates as timestamps at midnight
+fn date(s: &str) -> Option<NaiveDateTime> {
+    NaiveDate::parse_from_str(s, "%Y-%m-%d")
+        .ok()?
+        .and_hms_opt(0, 0, 0)
+}
+
+/// test case: all parts of a todo.txt line
+#[test]
+fn test_todo_txt_entry() {
+    let entry =
+        Entry::from_todo_txt("(A) 2024-06-01 Call mom +family @phone due:2024-06-20".into())
+            .unwrap();
+    assert_eq!(entry.status, Status::Open);
+    assert_eq!(entry.priority, Some('A'));
+    assert_eq!(entry.created, date("2024-06-01"));
+    assert_eq!(entry.text, "Call mom +family @phone");
+    assert_eq!(
+        entry.deadline,
+        NaiveDateTime::parse_from_str("2024-06-20 23:59", "%Y-%m-%d %H:%M").ok()
+    );
+
+    let entry = Entry::from_todo_txt("x 2024-06-21 2024-06-01 Call mom pri:B".into()).unwrap();
+    assert_eq!(entry.status, Status::Done);
+    assert_eq!(entry.completed, date("2024-06-21"));
+    assert_eq!(entry.created, date("2024-06-01"));
+    assert_eq!(entry.priority, Some('B'));
+    assert_eq!(entry.text, "Call mom");
+
+    // statuses todo.txt doesn't have survive a round-trip
+    for (md, txt) in [
+        ("- [-] Call dad", "x Call dad status:cancelled"),
+        ("- [>] Call dad", "Call dad status:deferred"),
+        ("- [/] Call dad", "Call dad status:in-progress"),
+    ] {
+        let entry = Entry::from_md(md.to_string()).unwrap();
+        assert_eq!(entry.to_todo_txt(), txt);
+        assert_eq!(
+            Entry::from_todo_txt(txt.into()).unwrap().status,
+            entry.status
+        );
+    }
+
+    // not a priority or date
+    let entry = Entry::from_todo_txt("(a) 2024-13-01 x due:soon".into()).unwrap();
+    assert_eq!(entry.text, "(a) 2024-13-01 x due:soon");
+    assert!(Entry::from_todo_txt("  ".into()).is_err());
+}
+
+/// test case: unchanged lines are kept, changed ones rewritten
+#[test]
+fn test_todo_txt_document() {
+    let txt = "(A)  Call mom +family due:2024-06-20T18:30\n\n2024-06-01 Water plants @home\n";
+    let mut document = Document::from_todo_txt(txt.to_string()).unwrap();
+    assert_eq!(
+        document.to_todo_txt(),
+        "(A)  Call mom +family due:2024-06-20T18:30\n2024-06-01 Water plants @home\n"
+    );
+
+    document.entry_mut(&[0, 0]).unwrap().status = Status::Done;
+    assert_eq!(
+        document.to_todo_txt(),
+        "x Call mom +family due:2024-06-20T18:30 pri:A\n2024-06-01 Water plants @home\n"
+    );
+}
+
+/// test case: converting markdown flattens subtasks and drops everything else
+#[test]
+fn test_markdown_to_todo_txt() {
+    let md = "# Chores\n\nprose\n\n- [x] (2024-06-20 16:00) Get groceries\n    - [ ] Milk\n";
+    let document = Document::from_md(md.to_string()).unwrap();
+    assert_eq!(
+        document.to_todo_txt(),
+        "x Get groceries due:2024-06-20T16:00\nMilk\n"
+    );
+}
@@ -0,0 +1,113 @@
+use crate::*;
+
+use chrono::NaiveDateTime;
+use std::cmp::Ordering::Equal;
+
+fn datetime(s: &str) -> NaiveDateTime {
+    NaiveDateTime::parse_from_str(s, "%Y-%m-%d %H:%M").unwrap()
+}
+
+/// test case: filters by completion, deadline and text
+#[test]
+fn test_filter_matches() {
+    // a wednesday
+    let now = datetime("2024-06-19 12:00");
+    let entry = |md: &str| Entry::from_md(md.to_string()).unwrap();
+
+    let overdue = entry("- [ ] (2024-06-17 09:00) Water plants");
+    let later = entry("- [ ] (2024-06-23 20:00) Do the dishes");
+    let next_week = entry("- [ ] (2024-06-24 09:00) Take out the trash");
+    let done = entry("- [x] (2024-06-17 09:00) Get groceries");
+
+    assert!(Filter::Open.matches(&overdue, now));
+    assert!(!Filter::Open.matches(&done, now));
+    assert!(Filter::Overdue.matches(&overdue, now));
+    assert!(!Filter::Overdue.matches(&later, now));
+    assert!(!Filter::Overdue.matches(&done, now));
+    assert!(Filter::DueThisWeek.matches(&overdue, now));
+    assert!(Filter::DueThisWeek.matches(&later, now));
+    assert!(!Filter::DueThisWeek.matches(&next_week, now));
+    assert!(Filter::Text("DISHES".into()).matches(&later, now));
+    assert!(!Filter::Text("dishes".into()).matches(&overdue, now));
+}
+
+/// test case: the view hides and reorders entries without touching the document
+#[test]
+fn test_view_paths() {
+    let md = "- [x] b\n    - [ ] b2\n    - [x] b1\n- [x] a\n- [ ] c\n";
+    let document = Document::from_md(md.to_string()).unwrap();
+    let open = |_: &[usize], entry: &Entry| !entry.status.is_closed();
+
+    // parents of shown entries stay visible
+    assert_eq!(
+        view_paths(&document, true, |_, _| Equal, open),
+        vec![vec![0, 0], vec![0, 0, 0], vec![0, 2]]
+    );
+    assert_eq!(
+        view_paths(&document, true, |a, b| a.text.cmp(&b.text), |_, _| true),
+        vec![
+            vec![0, 1],
+            vec![0, 0],
+            vec![0, 0, 1],
+            vec![0, 0, 0],
+            vec![0, 2]
+        ]
+    );
+    assert_eq!(document.to_md(), md);
+}
+
+/// test case: priority sorts on its own and breaks ties between equal deadlines
+#[test]
+fn test_sort_by_priority() {
+    let md = "- [ ] (2024-06-20 16:00) a\n- [ ] (2024-06-20 16:00) (A) b\n- [ ] ! c\n- [ ] (2024-06-19 09:00) d\n";
+    let document = Document::from_md(md.to_string()).unwrap();
+    let texts = |mode: SortMode| {
+        view_paths(&document, true, |a, b| mode.compare(a, b), |_, _| true)
+            .iter()
+            .map(|path| document.entry(path).unwrap().text.clone())
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(texts(SortMode::ByPriority), ["b", "c", "a", "d"]);
+    assert_eq!(texts(SortMode::ByDeadlineAscending), ["c", "d", "b", "a"]);
+    assert_eq!(texts(SortMode::ByDeadlineDescending), ["b", "a", "d", "c"]);
+}
+
+/// test case: urgency of open entries relative to now
+#[test]
+fn test_urgency() {
+    let now = datetime("2024-06-19 12:00");
+    let due_soon = chrono::Duration::hours(24);
+    let urgency = |md: &str| Urgency::of(&Entry::from_md(md.to_string()).unwrap(), now, due_soon);
+
+    assert_eq!(
+        urgency("- [ ] (2024-06-19 11:59) a"),
+        Some(Urgency::Overdue)
+    );
+    assert_eq!(
+        urgency("- [ ] (2024-06-19 23:00) a"),
+        Some(Urgency::DueToday)
+    );
+    assert_eq!(
+        urgency("- [ ] (2024-06-20 12:00) a"),
+        Some(Urgency::DueSoon)
+    );
+    assert_eq!(urgency("- [ ] (2024-06-20 12:01) a"), None);
+    assert_eq!(urgency("- [x] (2024-06-18 12:00) a"), None);
+    assert_eq!(urgency("- [ ] a"), None);
+}
+
+/// test case: relative deadlines
+#[test]
+fn test_relative_deadline() {
+    let now = datetime("2024-06-19 22:00");
+    let relative = |s: &str, done| relative_deadline(datetime(s), now, done);
+
+    assert_eq!(relative("2024-06-19 22:00", false), "now");
+    assert_eq!(relative("2024-06-19 22:45", false), "in 45m");
+    assert_eq!(relative("2024-06-20 01:00", false), "in 3h");
+    assert_eq!(relative("2024-06-20 23:00", false), "tomorrow");
+    assert_eq!(relative("2024-06-23 22:00", false), "in 4d");
+    assert_eq!(relative("2024-07-10 22:00", false), "in 3w");
+    assert_eq!(relative("2024-06-16 20:00", false), "3d overdue");
+    assert_eq!(relative("2024-06-16 20:00", true), "3d ago");
+}
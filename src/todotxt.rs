@@ -0,0 +1,189 @@
+use crate::*;
+
+use anyhow::bail;
+use chrono::{NaiveDate, NaiveDateTime, NaiveTime, Timelike};
+
+/// conversion from/to the todo.txt format (http://todotxt.org), in the same
+/// role as the `Markdown` trait. an entry is a single line of the form
+/// - open: `(A) 2024-06-01 Call mom +family @phone due:2024-06-20`
+/// - done: `x 2024-06-21 2024-06-01 Call mom +family @phone due:2024-06-20 pri:A`
+///
+/// `+project` and `@context` tags are kept as part of the text. statuses todo.txt
+/// doesn't know are kept in a `status:` tag, e.g. `x Call mom status:cancelled`
+pub trait TodoTxt {
+    fn to_todo_txt(&self) -> String;
+    fn from_todo_txt(txt: String) -> anyhow::Result<Self>
+    where
+        Self: Sized;
+}
+
+impl TodoTxt for Entry {
+    fn to_todo_txt(&self) -> String {
+        let mut parts = vec![];
+        if self.status.is_closed() {
+            parts.push("x".to_string());
+            parts.extend(self.completed.map(|stamp| stamp.date().to_string()));
+        } else if let Some(priority) = self.priority {
+            parts.push(format!("({})", priority));
+        }
+        // a single date after `x` would be the completion date
+        if !self.status.is_closed() || self.completed.is_some() {
+            parts.extend(self.created.map(|stamp| stamp.date().to_string()));
+        }
+        parts.push(self.text.clone());
+        if let Some(deadline) = self.deadline {
+            parts.push(match deadline.time() == default_due_time() {
+                true => format!("due:{}", deadline.format("%Y-%m-%d")),
+                false => format!("due:{}", deadline.format("%Y-%m-%dT%H:%M")),
+            });
+        }
+        if let (true, Some(priority)) = (self.status.is_closed(), self.priority) {
+            parts.push(format!("pri:{}", priority));
+        }
+        if !matches!(self.status, Status::Open | Status::Done) {
+            parts.push(format!("status:{}", self.status.name()));
+        }
+        parts.retain(|part| !part.is_empty());
+        parts.join(" ")
+    }
+
+    fn from_todo_txt(txt: String) -> anyhow::Result<Self> {
+        let line = txt.trim();
+        if line.is_empty() {
+            bail!("Empty todo.txt line");
+        }
+        let mut entry = Entry {
+            source: Some(txt.clone()),
+            ..Default::default()
+        };
+
+        let mut rest = line;
+        if let Some(done) = rest.strip_prefix("x ") {
+            entry.status = Status::Done;
+            rest = done.trim_start();
+        }
+        if let Some(priority) = parse_priority(rest) {
+            entry.priority = Some(priority);
+            rest = rest[3..].trim_start();
+        }
+        let mut dates = vec![];
+        while let Some((date, after)) = parse_date(rest) {
+            dates.push(date);
+            rest = after;
+            if dates.len() == 2 || !entry.status.is_closed() {
+                break;
+            }
+        }
+        match (entry.status.is_closed(), dates.as_slice()) {
+            (true, [completed, created]) => {
+                entry.completed = Some(completed.and_time(NaiveTime::MIN));
+                entry.created = Some(created.and_time(NaiveTime::MIN));
+            }
+            (true, [completed]) => entry.completed = Some(completed.and_time(NaiveTime::MIN)),
+            (false, [created]) => entry.created = Some(created.and_time(NaiveTime::MIN)),
+            _ => (),
+        }
+
+        let mut words = vec![];
+        for word in rest.split(' ') {
+            if let Some(due) = word.strip_prefix("due:").and_then(parse_due) {
+                entry.deadline.get_or_insert(due);
+            } else if let Some(priority) = word.strip_prefix("pri:").and_then(parse_priority_letter)
+            {
+                entry.priority.get_or_insert(priority);
+            } else if let Some(status) = word.strip_prefix("status:").and_then(Status::from_name) {
+                // only refines whether the line is completed
+                if status.is_closed() == entry.status.is_closed() {
+                    entry.status = status;
+                }
+            } else {
+                words.push(word);
+            }
+        }
+        entry.text = words.join(" ").trim().to_string();
+        entry.parse_tags();
+        Ok(entry)
+    }
+}
+
+impl TodoTxt for Document {
+    /// one line per entry; subtasks are flattened and everything that isn't an
+    /// entry (headings, prose, ...) is left out
+    fn to_todo_txt(&self) -> String {
+        let newline = if self.crlf { "\r\n" } else { "\n" };
+        let mut txt = String::new();
+        for path in self.entry_paths(false) {
+            let entry = self.entry(&path).unwrap();
+            txt += &entry.to_todo_txt_preserving_source();
+            txt += newline;
+        }
+        txt
+    }
+
+    fn from_todo_txt(txt: String) -> anyhow::Result<Self> {
+        let mut document = Document {
+            crlf: txt.contains("\r\n"),
+            ..Default::default()
+        };
+        for line in txt.lines().filter(|line| !line.trim().is_empty()) {
+            let entry = Entry::from_todo_txt(line.to_string())?;
+            document.push_entry(0, entry);
+        }
+        Ok(document)
+    }
+}
+
+impl Entry {
+    /// like `Entry::to_todo_txt`, but reuses the line the entry was parsed
+    /// from, as long as the entry hasn't been modified since
+    pub fn to_todo_txt_preserving_source(&self) -> String {
+        let txt = self.to_todo_txt();
+        match &self.source {
+            Some(source)
+                if Entry::from_todo_txt(source.clone()).is_ok_and(|e| e.to_todo_txt() == txt) =>
+            {
+                source.trim().to_string()
+            }
+            _ => txt,
+        }
+    }
+}
+
+/// deadlines given as a date only are due at the end of the day
+fn default_due_time() -> NaiveTime {
+    NaiveTime::from_hms_opt(23, 59, 0).unwrap()
+}
+
+/// priority of a line starting with `(A) `
+fn parse_priority(s: &str) -> Option<char> {
+    match s.as_bytes() {
+        [b'(', _, b')', b' ', ..] => parse_priority_letter(&s[1..2]),
+        _ => None,
+    }
+}
+
+fn parse_priority_letter(s: &str) -> Option<char> {
+    let mut chars = s.chars();
+    match (chars.next(), chars.next()) {
+        (Some(c), None) if c.is_ascii_uppercase() => Some(c),
+        _ => None,
+    }
+}
+
+/// the `YYYY-MM-DD` date at the start of `s` and what follows it
+fn parse_date(s: &str) -> Option<(NaiveDate, &str)> {
+    let (word, rest) = s.split_once(' ').unwrap_or((s, ""));
+    let date = NaiveDate::parse_from_str(word, "%Y-%m-%d").ok()?;
+    Some((date, rest.trim_start()))
+}
+
+/// `YYYY-MM-DD` or `YYYY-MM-DDTHH:MM`
+fn parse_due(s: &str) -> Option<NaiveDateTime> {
+    NaiveDateTime::parse_from_str(s, "%Y-%m-%dT%H:%M")
+        .ok()
+        .or_else(|| {
+            let date = NaiveDate::parse_from_str(s, "%Y-%m-%d").ok()?;
+            Some(date.and_time(default_due_time()))
+        })
+        .map(|due| due.with_second(0).unwrap())
+}
@@ -3,8 +3,10 @@ use crate::*;
 
 use std::cmp::Ordering::*;
 use std::io;
+use std::ops::Range;
 
 use anyhow::{anyhow, Result};
+use regex::Regex;
 
 use crossterm::{
     cursor::{self, MoveTo, RestorePosition, SavePosition},
@@ -25,18 +27,69 @@ where
     pub active_color_pair: (Color, Color),
     pub inactive_color_pair: (Color, Color),
     pub inactive_done_color_pair: (Color, Color),
+    pub inactive_cancelled_color_pair: (Color, Color),
+    pub in_progress_color_pair: (Color, Color),
+    pub deferred_color_pair: (Color, Color),
+    pub overdue_color_pair: (Color, Color),
+    pub due_today_color_pair: (Color, Color),
+    pub due_soon_color_pair: (Color, Color),
     pub header_color_pair: (Color, Color),
+    /// colors of the priority column for priorities `A`, `B`, `C` and lower
+    pub priority_colors: [Color; 4],
+    pub tag_color: Color,
+    pub assignee_color: Color,
+    /// how far ahead deadlines are highlighted as due soon
+    pub due_soon: chrono::Duration,
+    pub deadline_display: DeadlineDisplay,
+    /// statuses `<space>` cycles through
+    pub status_cycle: Vec<Status>,
+    /// whether completed recurring entries are moved to the `Archive` section
+    pub archive_recurring: bool,
+    /// width of the deadline column, fitting all visible deadlines
+    deadline_width: usize,
+    /// width of the priority column, empty unless a visible entry has a priority
+    priority_width: usize,
     pub ostream: T,
     pub scrolloff: usize,
     pub current_sort_mode: SortMode,
+    pub filter: Filter,
     pub document: Document,
-    pub original_document: Document,
     pub width: usize,
     pub save_on_quit: bool,
     pub height: usize,
     pub active_entry_idx: usize,
     pub current_scroll_offset: usize,
-    queue_sort_update: bool,
+    pub editor: LineEditor,
+    /// the entry currently being edited in the line editor, if any
+    pub edit_target: Option<EditTarget>,
+    pub datetime_editor: DatetimeEditor,
+    /// the entry whose deadline is currently being edited, if any
+    pub datetime_target: Option<EntryPath>,
+    /// text prompt shown in the status line, if any
+    pub prompt: Option<Prompt>,
+    /// list of tags shown instead of the entries to pick some to filter by, if open
+    pub tag_picker: Option<TagPicker>,
+    /// notes of an entry shown instead of the entries, if open
+    pub notes_pane: Option<NotesPane>,
+    /// message shown in the status line until the next key press
+    pub message: Option<String>,
+    pub history: History,
+    /// the last search, whose matches are highlighted
+    pub search: Option<Search>,
+    /// `search` compiled, if it is valid
+    search_regex: Option<Regex>,
+}
+
+/// an entry opened in the line editor
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EditTarget {
+    pub path: EntryPath,
+    /// whether the entry was created for this edit, and should be dropped
+    /// again if the edit is cancelled or leaves it empty
+    pub is_new: bool,
+    /// index of the note line being edited instead of the entry's text. new
+    /// lines are only inserted there once the edit is committed
+    pub note: Option<usize>,
 }
 
 impl<T> Ui<T>
@@ -52,24 +105,58 @@ where
         let active_color_pair = (Color::Black, Color::Yellow);
         let inactive_color_pair = (Color::Reset, Color::Reset);
         let inactive_done_color_pair = (Color::DarkGrey, Color::Reset);
+        let inactive_cancelled_color_pair = (Color::DarkRed, Color::Reset);
+        let in_progress_color_pair = (Color::Blue, Color::Reset);
+        let deferred_color_pair = (Color::DarkYellow, Color::Reset);
         let header_color_pair = (Color::Yellow, Color::Reset);
+        let overdue_color_pair = (Color::Red, Color::Reset);
+        let due_today_color_pair = (Color::Magenta, Color::Reset);
+        let due_soon_color_pair = (Color::Cyan, Color::Reset);
+        let priority_colors = [Color::Red, Color::Yellow, Color::Green, Color::DarkGrey];
+        let tag_color = Color::DarkCyan;
+        let assignee_color = Color::DarkMagenta;
 
         let mut ui = Ui {
             active_color_pair,
             save_on_quit: true,
             inactive_color_pair,
             inactive_done_color_pair,
+            inactive_cancelled_color_pair,
+            in_progress_color_pair,
+            deferred_color_pair,
             header_color_pair,
+            overdue_color_pair,
+            due_today_color_pair,
+            due_soon_color_pair,
+            priority_colors,
+            tag_color,
+            assignee_color,
+            due_soon: chrono::Duration::hours(24),
+            deadline_display: DeadlineDisplay::Absolute,
+            status_cycle: vec![Status::Open, Status::Done],
+            archive_recurring: false,
+            deadline_width: ABSOLUTE_DEADLINE_WIDTH,
+            priority_width: 0,
             current_sort_mode: SortMode::Default,
+            filter: Filter::All,
             active_entry_idx: 0,
             scrolloff: 8,
             current_scroll_offset: 0,
-            original_document: document.clone(),
             document,
             ostream,
             width,
             height,
-            queue_sort_update: false,
+            editor: LineEditor::default(),
+            edit_target: None,
+            datetime_editor: DatetimeEditor::default(),
+            datetime_target: None,
+            prompt: None,
+            tag_picker: None,
+            notes_pane: None,
+            message: None,
+            history: History::default(),
+            search: None,
+            search_regex: None,
         };
 
         queue!(
@@ -124,69 +211,356 @@ where
     /// draws the entire ui including unchanged parts
     pub fn draw(&mut self) -> Result<()> {
         self.update_dimensions()?;
+        self.clamp_focus();
         self.update_scroll_offset();
+        self.update_deadline_width();
+        self.update_priority_width();
         self.clear().unwrap();
 
-        self.apply_sort_mode();
-
         self.draw_header();
-        for (i, entry) in self
-            .document
-            .entries
+        if self.tag_picker.is_some() {
+            self.draw_tag_picker();
+            self.draw_status_line();
+            self.ostream.flush()?;
+            return Ok(());
+        }
+        if self.notes_pane.is_some() {
+            self.draw_notes_pane();
+            self.draw_status_line();
+            self.ostream.flush()?;
+            return Ok(());
+        }
+        let active_row_idx = self.active_row_idx();
+        let rows = self.visible_rows();
+        for (i, row) in rows
             .iter()
+            .enumerate()
             .skip(self.current_scroll_offset)
+            .take(self.inner_height())
+        {
+            match row {
+                Row::Section(idx) => self.draw_section_header(*idx),
+                Row::Entry(path) => self.draw_entry(path, i == active_row_idx),
+            }
+        }
+        self.draw_status_line();
+
+        self.ostream.flush()?;
+        Ok(())
+    }
+
+    /// draws the tag picker in place of the entries
+    fn draw_tag_picker(&mut self) {
+        let picker = self.tag_picker.clone().unwrap();
+        let first = (picker.cursor + 1).saturating_sub(self.inner_height());
+        for (i, label) in picker
+            .labels
+            .iter()
             .enumerate()
+            .skip(first)
+            .take(self.inner_height())
         {
-            if i >= self.inner_height() {
-                break;
+            let checkbox = if picker.picked.contains(label) {
+                "[x]"
+            } else {
+                "[ ]"
+            };
+            let line = format!("  {} {}", checkbox, label);
+            let line = format!("{:1$}", line, self.width);
+            let (fg, bg) = match (i == picker.cursor, label.starts_with('#')) {
+                (true, _) => self.active_color_pair,
+                (false, true) => (self.tag_color, Color::Reset),
+                (false, false) => (self.assignee_color, Color::Reset),
+            };
+            queue!(self.ostream, SetForegroundColor(fg), SetBackgroundColor(bg));
+            write!(self.ostream, "{}\r\n", line);
+        }
+        queue!(
+            self.ostream,
+            SetBackgroundColor(Color::Reset),
+            SetForegroundColor(Color::Reset)
+        );
+    }
+
+    /// draws the notes of the entry they belong to in place of the entries,
+    /// below its text. the note line being edited is drawn with the line editor
+    fn draw_notes_pane(&mut self) {
+        let pane = self.notes_pane.clone().unwrap();
+        let Some(entry) = self.document.entry(&pane.path) else {
+            self.notes_pane = None;
+            return;
+        };
+        let title = format!("  [{}] {}", entry.status.to_char(), entry.text);
+        let mut lines: Vec<Option<String>> = entry.notes().into_iter().map(Some).collect();
+        let editing = self
+            .edit_target
+            .as_ref()
+            .and_then(|t| t.note.map(|i| (i, t.is_new)));
+        if let Some((i, true)) = editing {
+            lines.insert(i.min(lines.len()), None);
+        }
+
+        let (fg, bg) = self.header_color_pair;
+        queue!(self.ostream, SetForegroundColor(fg), SetBackgroundColor(bg));
+        let title: String = title.chars().take(self.width).collect();
+        let title = format!("{:1$}", title, self.width);
+        write!(self.ostream, "{}\r\n", title.bold());
+        queue!(self.ostream, SetForegroundColor(Color::Reset));
+        if lines.is_empty() {
+            let hint = "  no notes yet, `o` adds one";
+            queue!(
+                self.ostream,
+                SetForegroundColor(self.inactive_done_color_pair.0)
+            );
+            write!(self.ostream, "{}\r\n", hint);
+        }
+
+        let height = self.inner_height().saturating_sub(1);
+        let first = (pane.cursor + 1).saturating_sub(height);
+        for (i, line) in lines.iter().enumerate().skip(first).take(height) {
+            if editing.is_some_and(|(e, _)| e == i) {
+                let (fg, bg) = self.active_color_pair;
+                queue!(self.ostream, SetForegroundColor(fg), SetBackgroundColor(bg));
+                self.draw_editor_line("  ".into());
+                continue;
             }
-            let mut bold = false;
-            let (fg, bg) = match i == self.active_entry_idx - self.current_scroll_offset {
-                true => {
-                    bold = true;
-                    self.active_color_pair
-                }
-                false if entry.done => self.inactive_done_color_pair,
+            let line = format!("  {}", line.as_deref().unwrap_or_default());
+            let line: String = line.chars().take(self.width).collect();
+            let line = format!("{:1$}", line, self.width);
+            let (fg, bg) = match i == pane.cursor {
+                true => self.active_color_pair,
                 false => self.inactive_color_pair,
             };
-
             queue!(self.ostream, SetForegroundColor(fg), SetBackgroundColor(bg));
-            let mut line = String::with_capacity(self.width);
-            if entry.done {
-                line += "  [x] ";
-            } else {
-                line += "  [ ] ";
-            }
+            write!(self.ostream, "{}\r\n", line);
+        }
+        queue!(
+            self.ostream,
+            SetBackgroundColor(Color::Reset),
+            SetForegroundColor(Color::Reset)
+        );
+    }
 
-            if let Some(deadline) = entry.deadline {
-                line += &format!("{}", deadline.format("(%Y-%m-%d %H:%M)"));
-            } else {
-                line += &" ".repeat("(YYYY-mm-dd HH:MM)".len());
+    /// draws a single entry row. the entry being edited is drawn with the
+    /// contents and cursor of the line editor instead
+    pub fn draw_entry(&mut self, path: &[usize], active: bool) {
+        let entry = self.document.entry(path).unwrap().clone();
+        // drawn on every poll tick, so entries change color as time passes
+        let now = chrono::Local::now().naive_local();
+        let (fg, bg) = match (active, Urgency::of(&entry, now, self.due_soon)) {
+            (true, _) => self.active_color_pair,
+            (false, _) if entry.status == Status::Done => self.inactive_done_color_pair,
+            (false, _) if entry.status == Status::Cancelled => self.inactive_cancelled_color_pair,
+            (false, Some(Urgency::Overdue)) => self.overdue_color_pair,
+            (false, Some(Urgency::DueToday)) => self.due_today_color_pair,
+            (false, Some(Urgency::DueSoon)) => self.due_soon_color_pair,
+            (false, None) if entry.status == Status::InProgress => self.in_progress_color_pair,
+            (false, None) if entry.status == Status::Deferred => self.deferred_color_pair,
+            (false, None) => self.inactive_color_pair,
+        };
+
+        queue!(self.ostream, SetForegroundColor(fg), SetBackgroundColor(bg));
+        let mut line = String::with_capacity(self.width);
+        line += match (entry.has_subtasks(), entry.collapsed) {
+            (true, true) => "▸ ",
+            (true, false) => "▾ ",
+            (false, _) => "  ",
+        };
+        line += match entry.status {
+            Status::Open => "[ ] ",
+            Status::Done => "[✓] ",
+            Status::Cancelled => "[✗] ",
+            Status::InProgress => "[◐] ",
+            Status::Deferred => "[»] ",
+        };
+
+        let priority_column = line.chars().count();
+        let priority = entry.priority.map_or(String::new(), String::from);
+        line += &format!("{:1$}", priority, self.priority_width);
+
+        let deadline_column = line.chars().count();
+        let deadline = self.deadline_display.format(&entry, now);
+        line += &format!("{:1$}", deadline, self.deadline_width);
+        line += "    ";
+        line += &"  ".repeat(path.len() - 2);
+
+        if self.edit_target.as_ref().is_some_and(|t| t.path == path) {
+            self.draw_editor_line(line);
+            return;
+        }
+
+        let notes = if entry.has_notes() { " ≡" } else { "" };
+        let space = self
+            .width
+            .saturating_sub(line.chars().count() + notes.chars().count() + 1);
+        let text_column = line.chars().count();
+        let mut text = entry.text.clone();
+        let mut shown = text.chars().count();
+        if shown > space {
+            shown = space.saturating_sub(3);
+            text = text.chars().take(shown).collect::<String>() + "... ";
+        }
+        line += &text;
+        line += notes;
+        let space = self.width.saturating_sub(line.chars().count());
+        line += &" ".repeat(space);
+
+        if self.datetime_target.as_deref() == Some(path) {
+            // draw the deadline column using the datetime editor
+            let head: String = line.chars().take(deadline_column).collect();
+            let tail: String = line
+                .chars()
+                .skip(deadline_column + self.deadline_width)
+                .collect();
+            let padding = " ".repeat(self.deadline_width - ABSOLUTE_DEADLINE_WIDTH);
+            write!(self.ostream, "{}", head.bold());
+            for (part, current) in self.datetime_editor.render() {
+                match current {
+                    true => write!(self.ostream, "{}", part.bold().reverse()),
+                    false => write!(self.ostream, "{}", part.bold()),
+                };
+            }
+            write!(self.ostream, "{}{}\r\n", padding, tail.bold());
+        } else {
+            let highlights: Vec<_> = self
+                .search_ranges(&entry.text)
+                .into_iter()
+                .map(|r| text_column + r.start..text_column + r.end.min(shown))
+                .collect();
+            // the active row keeps its colors, completed entries stay grey
+            let mut colors = vec![];
+            if let (false, Some(p)) = (active, entry.priority) {
+                let color = self.priority_colors[(p as usize).saturating_sub('A' as usize).min(3)];
+                colors.push((priority_column..priority_column + 1, color));
+            }
+            if !active && !entry.status.is_closed() {
+                for tag in find_tags(&entry.text) {
+                    let color = match tag.kind {
+                        TagKind::Tag => self.tag_color,
+                        TagKind::Assignee => self.assignee_color,
+                    };
+                    let range =
+                        text_column + tag.range.start..text_column + tag.range.end.min(shown);
+                    colors.push((range, color));
+                }
             }
-            line += "    ";
+            self.write_highlighted(&line, &highlights, &colors, fg, active);
+            write!(self.ostream, "\r\n");
+        }
+        queue!(
+            self.ostream,
+            SetBackgroundColor(Color::Reset),
+            SetForegroundColor(Color::Reset)
+        );
+    }
 
-            let space = self.width - line.len() - 1;
-            let mut text = entry.text.clone();
-            if text.chars().count() > space {
-                text = text[0..space - 3].to_string() + "... ";
+    /// writes `line` with the chars at the indices in `highlights` reversed,
+    /// and those in the ranges of `colors` in the given color instead of `fg`
+    fn write_highlighted(
+        &mut self,
+        line: &str,
+        highlights: &[Range<usize>],
+        colors: &[(Range<usize>, Color)],
+        fg: Color,
+        bold: bool,
+    ) {
+        let chars: Vec<char> = line.chars().collect();
+        let style = |i: usize| {
+            let highlighted = highlights.iter().any(|r| r.contains(&i));
+            let color = colors.iter().find(|(r, _)| r.contains(&i)).map(|(_, c)| *c);
+            (highlighted, color)
+        };
+        let mut start = 0;
+        while start < chars.len() {
+            let (highlighted, color) = style(start);
+            let end = (start..chars.len())
+                .find(|&i| style(i) != (highlighted, color))
+                .unwrap_or(chars.len());
+            let part: String = chars[start..end].iter().collect();
+            if let Some(color) = color {
+                queue!(self.ostream, SetForegroundColor(color));
             }
-            line += &text;
-            let space = self.width - line.len();
-            line += &" ".repeat(space);
+            match (highlighted, bold) {
+                (true, _) => write!(self.ostream, "{}", part.bold().reverse()),
+                (false, true) => write!(self.ostream, "{}", part.bold()),
+                (false, false) => write!(self.ostream, "{}", part),
+            };
+            if color.is_some() {
+                queue!(self.ostream, SetForegroundColor(fg));
+            }
+            start = end;
+        }
+    }
+
+    /// draws `prefix` followed by the line editor's text, scrolled horizontally
+    /// so that the cursor is always visible
+    fn draw_editor_line(&mut self, prefix: String) {
+        let space = self.width.saturating_sub(prefix.chars().count() + 1);
+        let first = (self.editor.cursor + 1).saturating_sub(space);
+        let selection = self.editor.selection();
 
-            match bold {
-                true => write!(self.ostream, "{}\r\n", line.bold()),
-                false => write!(self.ostream, "{}\r\n", line),
+        write!(self.ostream, "{}", prefix.bold());
+        for i in first..first + space {
+            let c = self.editor.text.get(i).copied().unwrap_or(' ');
+            let highlighted = i == self.editor.cursor
+                || selection.is_some_and(|(start, end)| (start..end).contains(&i));
+            match highlighted {
+                true => write!(self.ostream, "{}", c.bold().reverse()),
+                false => write!(self.ostream, "{}", c.bold()),
             };
-            queue!(
-                self.ostream,
-                SetBackgroundColor(Color::Reset),
-                SetForegroundColor(Color::Reset)
-            );
         }
+        write!(self.ostream, " \r\n");
+        queue!(
+            self.ostream,
+            SetBackgroundColor(Color::Reset),
+            SetForegroundColor(Color::Reset)
+        );
+    }
 
-        self.ostream.flush()?;
-        Ok(())
+    /// draws the heading of the section at `idx`
+    pub fn draw_section_header(&mut self, idx: usize) {
+        let (fg, bg) = self.header_color_pair;
+        queue!(self.ostream, SetForegroundColor(fg), SetBackgroundColor(bg));
+
+        let section = &self.document.sections[idx];
+        let mut line = String::with_capacity(self.width);
+        line += &" ".repeat(self.text_column());
+        line += &format!("{} {}", "#".repeat(section.level), section.name);
+        let space = self.width.saturating_sub(line.chars().count());
+        line += &" ".repeat(space);
+        write!(self.ostream, "{}\r\n", line.bold());
+        queue!(
+            self.ostream,
+            SetForegroundColor(Color::Reset),
+            SetBackgroundColor(Color::Reset)
+        );
+    }
+
+    /// draws the open prompt or the last message in the bottom line,
+    /// and the number of search matches to the right of it
+    pub fn draw_status_line(&mut self) {
+        let line = match (&self.prompt, &self.message) {
+            (Some(prompt), _) => format!("{}{}", prompt.kind.label(), prompt.input),
+            (None, Some(message)) => message.clone(),
+            (None, None) if self.tag_picker.is_some() => {
+                "<space>: pick, <enter>: filter by picked tags, <esc>: cancel".into()
+            }
+            (None, None) if self.notes_pane.is_some() && self.edit_target.is_none() => {
+                "o/O: add line, <enter>: edit line, d: delete line, <esc>: close".into()
+            }
+            (None, None) => String::new(),
+        };
+        let count = self.search_count().unwrap_or_default();
+        queue!(self.ostream, MoveTo(0, self.height as u16 - 1));
+        let space = self.width.saturating_sub(count.chars().count() + 2);
+        let line: String = line.chars().take(space).collect();
+        write!(self.ostream, "{}", line);
+        if self.prompt.is_some() {
+            write!(self.ostream, "{}", " ".reverse());
+        }
+        let column = self.width.saturating_sub(count.chars().count() + 1);
+        queue!(self.ostream, MoveTo(column as u16, self.height as u16 - 1));
+        write!(self.ostream, "{}", count);
     }
 
     pub fn draw_header(&mut self) {
@@ -195,10 +569,28 @@ where
 
         let mut line = String::with_capacity(self.width);
         line += "  [todue] ";
-        line += &" ".repeat("  [x] (YYYY-mm-dd HH:MM)    ".len() - line.len());
+        line += &" ".repeat(self.text_column().saturating_sub(line.len()));
         line += &self.document.title.clone().unwrap_or("TODO".into());
-        let space = self.width - line.len();
+        let mode = match (&self.edit_target, &self.editor.mode) {
+            (None, _) if self.datetime_target.is_some() => "-- DEADLINE --  ",
+            (None, _) if self.tag_picker.is_some() => "-- TAGS --  ",
+            (None, _) if self.notes_pane.is_some() => "-- NOTES --  ",
+            (None, _) => "",
+            (Some(_), EditMode::Normal) => "-- EDIT --  ",
+            (Some(_), EditMode::Insert) => "-- INSERT --  ",
+            (Some(_), EditMode::Visual) => "-- VISUAL --  ",
+            (Some(_), EditMode::Replace) => "-- REPLACE --  ",
+        };
+        let status = match &self.filter {
+            Filter::All => mode.to_string(),
+            filter => format!("[{}]  {}", filter.label(), mode),
+        };
+        let status = status.as_str();
+        let space = self
+            .width
+            .saturating_sub(line.chars().count() + status.chars().count());
         line += &" ".repeat(space);
+        line += status;
         write!(self.ostream, "{}\r\n", line);
         write!(self.ostream, "{}\r\n", "—".repeat(self.width));
         queue!(
@@ -208,21 +600,56 @@ where
         );
     }
 
-    /// update the index of the first *shown* entry using `self.scrolloff`
+    /// fits the deadline column to the visible deadlines. absolute deadlines
+    /// and the datetime editor always fit
+    fn update_deadline_width(&mut self) {
+        let now = chrono::Local::now().naive_local();
+        let widest = self
+            .visible_entries()
+            .iter()
+            .filter_map(|path| self.document.entry(path))
+            .map(|entry| self.deadline_display.format(entry, now).chars().count())
+            .max()
+            .unwrap_or(0);
+        self.deadline_width = match self.deadline_display {
+            DeadlineDisplay::Relative if self.datetime_target.is_none() => widest,
+            _ => widest.max(ABSOLUTE_DEADLINE_WIDTH),
+        };
+    }
+
+    fn update_priority_width(&mut self) {
+        let any_priority = self
+            .visible_entries()
+            .iter()
+            .filter_map(|path| self.document.entry(path))
+            .any(|entry| entry.priority.is_some());
+        self.priority_width = if any_priority { "A ".len() } else { 0 };
+    }
+
+    /// column at which entry texts start (at the top level)
+    fn text_column(&self) -> usize {
+        "  [x] ".len() + self.priority_width + self.deadline_width + "    ".len()
+    }
+
+    /// switches between absolute, relative and both kinds of deadlines
+    pub fn cycle_deadline_display(&mut self) {
+        self.deadline_display = self.deadline_display.next();
+    }
+
+    /// update the index of the first *shown* row using `self.scrolloff`
     pub fn update_scroll_offset(&mut self) {
-        if self.current_scroll_offset + self.scrolloff >= self.active_entry_idx {
-            let diff =
-                (self.current_scroll_offset + self.scrolloff).abs_diff(self.active_entry_idx);
+        let active_row_idx = self.active_row_idx();
+        if self.current_scroll_offset + self.scrolloff >= active_row_idx {
+            let diff = (self.current_scroll_offset + self.scrolloff).abs_diff(active_row_idx);
             self.current_scroll_offset = self.current_scroll_offset.saturating_sub(diff);
         } else if (self.current_scroll_offset + self.inner_height()).saturating_sub(self.scrolloff)
-            <= self.active_entry_idx
+            <= active_row_idx
         {
             let diff = (self.current_scroll_offset + self.inner_height())
                 .saturating_sub(self.scrolloff)
-                .abs_diff(self.active_entry_idx);
+                .abs_diff(active_row_idx);
             self.current_scroll_offset = (self.current_scroll_offset + diff).min(
-                self.document
-                    .entries
+                self.visible_rows()
                     .len()
                     .saturating_sub(self.inner_height()),
             );
@@ -234,79 +661,866 @@ where
         self.height - 3
     }
 
+    /// paths of all entries currently shown, in display order
+    pub fn visible_entries(&self) -> Vec<EntryPath> {
+        self.view_paths(true)
+    }
+
+    /// paths of the entries passing the filter, in display order. entries
+    /// being edited are always included
+    fn view_paths(&self, skip_collapsed: bool) -> Vec<EntryPath> {
+        if self.filter == Filter::All && self.current_sort_mode == SortMode::Default {
+            return self.document.entry_paths(skip_collapsed);
+        }
+        let now = chrono::Local::now().naive_local();
+        let edited = [
+            self.edit_target.as_ref().map(|t| t.path.as_slice()),
+            self.datetime_target.as_deref(),
+        ];
+        view_paths(
+            &self.document,
+            skip_collapsed,
+            |a, b| self.current_sort_mode.compare(a, b),
+            |path, entry| self.filter.matches(entry, now) || edited.contains(&Some(path)),
+        )
+    }
+
+    /// all rows currently shown, i.e. visible entries and the headings of the
+    /// sections they are in. while filtering, sections without any visible
+    /// entries are hidden
+    pub fn visible_rows(&self) -> Vec<Row> {
+        let visible = self.visible_entries();
+        let mut rows = vec![];
+        for (i, section) in self.document.sections.iter().enumerate() {
+            let entries = visible.iter().filter(|path| path[0] == i);
+            let hidden = self.filter != Filter::All && entries.clone().next().is_none();
+            if section.heading.is_some() && !hidden {
+                rows.push(Row::Section(i));
+            }
+            rows.extend(entries.map(|path| Row::Entry(path.clone())));
+        }
+        rows
+    }
+
+    /// index of the focused entry's row within `Ui::visible_rows`
+    pub fn active_row_idx(&self) -> usize {
+        let active = self.active_entry_path().map(Row::Entry);
+        self.visible_rows()
+            .iter()
+            .position(|row| Some(row) == active.as_ref())
+            .unwrap_or(0)
+    }
+
+    /// path of the focused entry, if there is any
+    pub fn active_entry_path(&self) -> Option<EntryPath> {
+        self.visible_entries().get(self.active_entry_idx).cloned()
+    }
+
+    /// focuses the entry at `path`, if it is visible
+    pub fn select_entry_path(&mut self, path: &[usize]) {
+        if let Some(idx) = self.visible_entries().iter().position(|p| p == path) {
+            self.active_entry_idx = idx;
+        }
+    }
+
+    /// keeps the focus in bounds, e.g. after the focused entry was filtered out
+    fn clamp_focus(&mut self) {
+        let count = self.visible_entries().len();
+        self.active_entry_idx = self.active_entry_idx.min(count.saturating_sub(1));
+    }
+
+    /// applies `f`, which changes what is shown, keeping the focus on the same
+    /// entry if it is still visible
+    fn keep_focus(&mut self, f: impl FnOnce(&mut Self)) {
+        let path = self.active_entry_path();
+        f(self);
+        match path {
+            Some(path) if self.visible_entries().contains(&path) => self.select_entry_path(&path),
+            _ => self.clamp_focus(),
+        }
+    }
+
     pub fn move_selection(&mut self, dir: MoveDirection) -> Result<()> {
+        let count = self.visible_entries().len();
+        if count == 0 {
+            return Ok(());
+        }
         match dir {
-            Down => {
-                self.active_entry_idx = (self.active_entry_idx + 1) % self.document.entries.len()
-            }
+            Down => self.active_entry_idx = (self.active_entry_idx + 1) % count,
             Up => {
-                self.active_entry_idx = ((self.active_entry_idx as isize - 1)
-                    .rem_euclid(self.document.entries.len() as isize))
-                    as usize
+                self.active_entry_idx =
+                    ((self.active_entry_idx as isize - 1).rem_euclid(count as isize)) as usize
             }
         }
         Ok(())
     }
 
     pub fn move_selection_to_bottom(&mut self) {
-        self.active_entry_idx = self.document.entries.len() - 1;
+        self.active_entry_idx = self.visible_entries().len().saturating_sub(1);
     }
 
     pub fn move_selection_to_top(&mut self) {
         self.active_entry_idx = 0;
     }
 
+    /// moves the focused entry (including its subtasks) among its siblings
     pub fn move_selected_entry(&mut self, dir: MoveDirection) {
-        let swap_idx = match dir {
-            Down => (self.active_entry_idx + 1) % self.document.entries.len(),
-            Up => {
-                ((self.active_entry_idx as isize - 1)
-                    .rem_euclid(self.document.entries.len() as isize)) as usize
-            }
+        let Some(path) = self.active_entry_path() else {
+            return;
         };
-        self.document.entries.swap(self.active_entry_idx, swap_idx);
-        self.move_selection(dir);
+        if self.current_sort_mode != SortMode::Default {
+            self.message = Some("Entries can only be moved in the default sort mode".into());
+            return;
+        }
+        self.begin_transaction();
+        if let Some(new_path) = self.document.move_entry(&path, dir) {
+            self.select_entry_path(&new_path);
+        }
+        self.end_transaction();
     }
 
+    /// moves the focused entry on to the next status. completing a recurring
+    /// entry creates its next occurrence, which gets the focus
     pub fn toggle_active_entry(&mut self) {
-        let state = self.document.entries[self.active_entry_idx].done;
-        self.document.entries[self.active_entry_idx].done = !state;
+        let Some(path) = self.active_entry_path() else {
+            return;
+        };
+        self.begin_transaction();
+        let now = chrono::Local::now().naive_local();
+        if let Some(entry) = self.document.entry_mut(&path) {
+            entry.set_status(entry.status.next_in(&self.status_cycle), now);
+            if entry.status == Status::Done && entry.recurrence.is_some() {
+                if let Some(next) =
+                    self.document
+                        .respawn_recurring(&path, self.archive_recurring, now)
+                {
+                    self.select_entry_path(&next);
+                    let deadline = self.document.entry(&next).unwrap().deadline.unwrap();
+                    self.message = Some(format!(
+                        "Next occurrence due {}",
+                        deadline.format("%Y-%m-%d %H:%M")
+                    ));
+                }
+            }
+        }
+        self.end_transaction();
     }
 
-    pub fn cycle_sort_mode(&mut self) {
-        self.current_sort_mode = match self.current_sort_mode {
-            SortMode::Default => SortMode::ByDeadlineDescending,
-            SortMode::ByDeadlineDescending => SortMode::ByDeadlineAscending,
-            SortMode::ByDeadlineAscending => SortMode::ByTextAscending,
-            SortMode::ByTextAscending => SortMode::ByTextDescending,
-            SortMode::ByTextDescending => SortMode::Default,
+    /// raises (or lowers) the focused entry's priority within `A` and the lowest
+    /// priority of its style, i.e. `E` (`⏬`) for obsidian's emoji and `C` for
+    /// the others. lowering the lowest priority removes it
+    pub fn change_active_priority(&mut self, raise: bool) {
+        let Some(path) = self.active_entry_path() else {
+            return;
+        };
+        self.begin_transaction();
+        if let Some(entry) = self.document.entry_mut(&path) {
+            if entry.priority.is_none() && entry.dialect == Dialect::Obsidian {
+                entry.priority_style = PriorityStyle::Emoji;
+            }
+            let lowest = match entry.priority_style {
+                PriorityStyle::Emoji => 'E',
+                _ => 'C',
+            };
+            let higher = |p: char| (p as u32).checked_sub(1).and_then(char::from_u32);
+            let lower = |p: char| (p as u32).checked_add(1).and_then(char::from_u32);
+            entry.priority = match (raise, entry.priority) {
+                (true, None) => Some('C'),
+                (true, Some(p)) => Some(higher(p).unwrap_or(p).clamp('A', lowest)),
+                (false, Some(p)) if ('A'..lowest).contains(&p) => lower(p),
+                (false, _) => None,
+            };
+        }
+        self.end_transaction();
+    }
+
+    /// collapses/expands the focused entry's subtasks. if it has none,
+    /// collapses the group it is part of and focuses its parent instead
+    pub fn toggle_collapse_active_group(&mut self) {
+        let Some(mut path) = self.active_entry_path() else {
+            return;
         };
-        self.queue_sort_update = true;
+        if !self.document.entry(&path).is_some_and(Entry::has_subtasks) {
+            path.pop();
+        }
+        if let Some(entry) = self.document.entry_mut(&path) {
+            entry.collapsed = !entry.collapsed;
+            self.select_entry_path(&path);
+        }
+    }
+
+    /// opens the focused entry in the line editor, in `mode`, with the cursor at
+    /// char index `cursor` (clamped to the end of the text). if `clear` is set,
+    /// the entry's text is discarded first
+    pub fn edit_active_entry(&mut self, cursor: usize, mode: EditMode, clear: bool) {
+        let Some(path) = self.active_entry_path() else {
+            return;
+        };
+        let text = match clear {
+            true => String::new(),
+            false => self.document.entry(&path).unwrap().text.clone(),
+        };
+        self.editor.start(&text, cursor, mode);
+        self.edit_target = Some(EditTarget {
+            path,
+            is_new: false,
+            note: None,
+        });
     }
 
-    pub fn apply_sort_mode(&mut self) {
-        if self.queue_sort_update {
-            match self.current_sort_mode {
-                SortMode::Default => self.document = self.original_document.clone(),
-                SortMode::ByDeadlineDescending => {
-                    self.document.entries.sort_by_key(|entry| entry.deadline);
-                    self.document.entries.reverse();
+    /// creates a new entry after (`Down`) or before (`Up`) the focused one and
+    /// opens it in the line editor. creating the entry, its text and deadline
+    /// are a single transaction
+    pub fn open_new_entry(&mut self, dir: MoveDirection) {
+        self.begin_transaction();
+        let now = chrono::Local::now().naive_local();
+        let path = match self.active_entry_path() {
+            Some(mut path) => {
+                let sibling = self.document.entry(&path).unwrap();
+                let entry = Entry {
+                    indent: sibling.indent.clone(),
+                    marker: sibling.marker,
+                    dialect: sibling.dialect,
+                    created: Some(stamp(now)),
+                    ..Default::default()
+                };
+                if dir == Down {
+                    *path.last_mut().unwrap() += 1;
                 }
-                SortMode::ByDeadlineAscending => {
-                    self.document.entries.sort_by_key(|entry| entry.deadline)
+                self.document.insert_entry(&path, entry).unwrap()
+            }
+            None => {
+                let section = self.document.default_section();
+                let entry = Entry {
+                    created: Some(stamp(now)),
+                    ..Default::default()
+                };
+                self.document.push_entry(section, entry).unwrap()
+            }
+        };
+        self.select_entry_path(&path);
+        self.editor.start("", 0, EditMode::Insert);
+        self.edit_target = Some(EditTarget {
+            path,
+            is_new: true,
+            note: None,
+        });
+    }
+
+    /// writes the line editor's text back into the entry being edited
+    pub fn commit_edit(&mut self) {
+        let Some(target) = self.edit_target.take() else {
+            return;
+        };
+        if let Some(idx) = target.note {
+            return self.commit_note_edit(&target.path, idx, target.is_new);
+        }
+        let text = self.editor.text().trim().to_string();
+        if text.is_empty() && target.is_new {
+            self.remove_entry(&target.path);
+            self.end_transaction();
+        } else if target.is_new {
+            if let Some(entry) = self.document.entry_mut(&target.path) {
+                entry.text = text;
+                entry.parse_tags();
+            }
+            // new entries get a deadline right away, the transaction ends with it
+            self.edit_deadline(&target.path);
+        } else {
+            self.begin_transaction();
+            if let Some(entry) = self.document.entry_mut(&target.path) {
+                if entry.text != text {
+                    entry.text = text;
+                    entry.parse_tags();
                 }
-                SortMode::ByTextAscending => self
-                    .document
-                    .entries
-                    .sort_by_key(|entry| entry.text.to_lowercase()),
-                SortMode::ByTextDescending => {
-                    self.document
-                        .entries
-                        .sort_by_key(|entry| entry.text.to_lowercase());
-                    self.document.entries.reverse();
+            }
+            self.end_transaction();
+        }
+    }
+
+    /// closes the line editor without applying changes
+    pub fn cancel_edit(&mut self) {
+        if let Some(target) = self.edit_target.take() {
+            if target.is_new && target.note.is_none() {
+                self.remove_entry(&target.path);
+                self.end_transaction();
+            }
+        }
+    }
+
+    pub fn is_editing(&self) -> bool {
+        self.edit_target.is_some()
+    }
+
+    /// opens the deadline of the entry at `path` in the datetime editor. closing
+    /// the datetime editor ends the currently open transaction
+    pub fn edit_deadline(&mut self, path: &[usize]) {
+        if let Some(entry) = self.document.entry(path) {
+            self.datetime_editor.start(entry.deadline);
+            self.datetime_target = Some(path.to_vec());
+        }
+    }
+
+    /// opens the focused entry's deadline in the datetime editor
+    pub fn edit_active_deadline(&mut self) {
+        if let Some(path) = self.active_entry_path() {
+            self.begin_transaction();
+            self.edit_deadline(&path);
+        }
+    }
+
+    /// moves on to the next segment of the deadline, or writes it back into
+    /// the entry after the last one
+    pub fn advance_datetime_editor(&mut self) {
+        if !self.datetime_editor.next_segment() {
+            self.commit_deadline();
+        }
+    }
+
+    /// writes the datetime editor's deadline back into the entry being edited
+    pub fn commit_deadline(&mut self) {
+        if let Some(path) = self.datetime_target.take() {
+            let deadline = self.datetime_editor.finish();
+            if let Some(entry) = self.document.entry_mut(&path) {
+                entry.deadline = Some(deadline);
+            }
+            self.end_transaction();
+        }
+    }
+
+    /// removes the deadline of the entry being edited and closes the datetime editor
+    pub fn remove_deadline(&mut self) {
+        if let Some(path) = self.datetime_target.take() {
+            if let Some(entry) = self.document.entry_mut(&path) {
+                entry.deadline = None;
+            }
+            self.end_transaction();
+        }
+    }
+
+    /// closes the datetime editor without applying changes
+    pub fn cancel_deadline(&mut self) {
+        if self.datetime_target.take().is_some() {
+            self.end_transaction();
+        }
+    }
+
+    pub fn is_editing_deadline(&self) -> bool {
+        self.datetime_target.is_some()
+    }
+
+    /// asks for a natural language deadline (`tomorrow 9am`, `in 3d`, ...) for
+    /// the entry in the datetime editor, or the focused entry otherwise
+    pub fn prompt_deadline(&mut self) {
+        let path = match &self.datetime_target {
+            Some(path) => Some(path.clone()),
+            None => self.active_entry_path(),
+        };
+        if let Some(path) = path {
+            self.open_prompt(PromptKind::Deadline(path));
+        }
+    }
+
+    pub fn open_prompt(&mut self, kind: PromptKind) {
+        self.prompt = Some(Prompt {
+            kind,
+            input: String::new(),
+        });
+    }
+
+    pub fn prompt_input(&mut self, c: char) {
+        if let Some(prompt) = &mut self.prompt {
+            prompt.input.push(c);
+        }
+        self.update_incremental_search();
+    }
+
+    pub fn prompt_backspace(&mut self) {
+        let Some(prompt) = &mut self.prompt else {
+            return;
+        };
+        match prompt.input.pop() {
+            Some(_) => self.update_incremental_search(),
+            None => self.cancel_prompt(),
+        }
+    }
+
+    pub fn cancel_prompt(&mut self) {
+        if let Some(Prompt {
+            kind: PromptKind::Search(prompt),
+            ..
+        }) = self.prompt.take()
+        {
+            // back to where the search started
+            self.set_search(prompt.previous);
+            if let Some(path) = prompt.origin {
+                self.reveal_entry(&path);
+            }
+        }
+    }
+
+    pub fn is_prompting(&self) -> bool {
+        self.prompt.is_some()
+    }
+
+    /// applies the prompt's input according to its kind and closes it.
+    /// invalid input keeps the prompt open and shows an error instead
+    pub fn submit_prompt(&mut self) {
+        let Some(prompt) = self.prompt.clone() else {
+            return;
+        };
+        match prompt.kind {
+            PromptKind::Deadline(path) => {
+                let now = chrono::Local::now().naive_local();
+                match parse_deadline(&prompt.input, now) {
+                    Ok(deadline) => {
+                        // the datetime editor's transaction is still open, if it was used
+                        if self.datetime_target.take().is_none() {
+                            self.begin_transaction();
+                        }
+                        if let Some(entry) = self.document.entry_mut(&path) {
+                            entry.deadline = Some(deadline);
+                        }
+                        self.end_transaction();
+                        self.prompt = None;
+                    }
+                    Err(e) => self.message = Some(e.to_string()),
+                }
+            }
+            PromptKind::Filter => {
+                self.prompt = None;
+                match prompt.input.is_empty() {
+                    true => self.set_filter(Filter::All),
+                    false => self.set_filter(Filter::Text(prompt.input)),
+                }
+            }
+            PromptKind::Search(search_prompt) => {
+                let search = match prompt.input.is_empty() {
+                    // an empty pattern repeats the previous search
+                    true => search_prompt.previous.map(|previous| Search {
+                        backward: search_prompt.backward,
+                        ..previous
+                    }),
+                    false => Some(search_prompt.search(&prompt.input)),
+                };
+                let Some(search) = search else {
+                    self.prompt = None;
+                    return;
+                };
+                if let Err(e) = search.compile() {
+                    self.message = Some(format!("Invalid pattern: {}", e));
+                    return;
+                }
+                self.prompt = None;
+                self.set_search(Some(search.clone()));
+                if let Some(path) = search_prompt.origin {
+                    self.reveal_entry(&path);
                 }
+                self.search_next(false);
             }
-            self.queue_sort_update = false;
+        }
+    }
+
+    /// opens the search prompt. `backward` searches towards the top
+    pub fn open_search(&mut self, backward: bool) {
+        self.open_prompt(PromptKind::Search(SearchPrompt {
+            backward,
+            regex: false,
+            origin: self.active_entry_path(),
+            previous: self.search.clone(),
+        }));
+    }
+
+    /// switches the open search prompt between plain text and regex patterns
+    pub fn toggle_search_regex(&mut self) {
+        if let Some(Prompt {
+            kind: PromptKind::Search(prompt),
+            ..
+        }) = &mut self.prompt
+        {
+            prompt.regex = !prompt.regex;
+        }
+        self.update_incremental_search();
+    }
+
+    /// highlights the matches of the pattern typed so far and focuses the
+    /// first one, starting from where the search was opened
+    fn update_incremental_search(&mut self) {
+        let Some(Prompt {
+            kind: PromptKind::Search(prompt),
+            input,
+        }) = self.prompt.clone()
+        else {
+            return;
+        };
+        if let Some(path) = &prompt.origin {
+            self.reveal_entry(path);
+        }
+        if input.is_empty() {
+            self.set_search(None);
+            return;
+        }
+        let search = prompt.search(&input);
+        self.set_search(Some(search.clone()));
+        if self.search_regex.is_some() {
+            self.jump_to_match(search.backward);
+        }
+    }
+
+    /// focuses the next match of the last search, in the search's direction
+    /// or the opposite one if `reverse` is set
+    pub fn search_next(&mut self, reverse: bool) {
+        let Some(search) = &self.search else {
+            self.message = Some("No previous search".into());
+            return;
+        };
+        let backward = search.backward != reverse;
+        let pattern = search.pattern.clone();
+        match self.jump_to_match(backward) {
+            None => self.message = Some(format!("Pattern not found: {}", pattern)),
+            Some(true) if backward => {
+                self.message = Some("search hit TOP, continuing at BOTTOM".into())
+            }
+            Some(true) => self.message = Some("search hit BOTTOM, continuing at TOP".into()),
+            Some(false) => (),
+        }
+    }
+
+    /// stops highlighting the matches of the last search
+    pub fn clear_search(&mut self) {
+        self.set_search(None);
+    }
+
+    fn set_search(&mut self, search: Option<Search>) {
+        self.search_regex = search
+            .as_ref()
+            .filter(|search| !search.pattern.is_empty())
+            .and_then(|search| search.compile().ok());
+        self.search = search;
+    }
+
+    /// paths of all entries matching the last search, including those in
+    /// collapsed groups
+    pub fn search_matches(&self) -> Vec<EntryPath> {
+        let Some(regex) = &self.search_regex else {
+            return vec![];
+        };
+        self.view_paths(false)
+            .into_iter()
+            .filter(|path| {
+                self.document
+                    .entry(path)
+                    .is_some_and(|entry| regex.is_match(&entry.text))
+            })
+            .collect()
+    }
+
+    /// char ranges of `text` matching the last search
+    fn search_ranges(&self, text: &str) -> Vec<Range<usize>> {
+        match &self.search_regex {
+            Some(regex) => match_ranges(regex, text),
+            None => vec![],
+        }
+    }
+
+    /// `[current/total]` matches of the last search
+    fn search_count(&self) -> Option<String> {
+        let search = self.search.as_ref().filter(|s| !s.pattern.is_empty())?;
+        if self.search_regex.is_none() {
+            return Some("[invalid pattern]".into());
+        }
+        let matches = self.search_matches();
+        let current = self
+            .active_entry_path()
+            .and_then(|path| matches.iter().position(|p| *p == path));
+        Some(match current {
+            Some(i) => format!("[{}/{}]", i + 1, matches.len()),
+            None => format!("[?/{}]", matches.len()),
+        })
+    }
+
+    /// focuses the next entry matching the last search, expanding collapsed
+    /// groups if needed. returns whether the search wrapped around, or `None`
+    /// if nothing matches
+    fn jump_to_match(&mut self, backward: bool) -> Option<bool> {
+        let all = self.view_paths(false);
+        let matches: Vec<usize> = self
+            .search_matches()
+            .iter()
+            .filter_map(|path| all.iter().position(|p| p == path))
+            .collect();
+        let current = self
+            .active_entry_path()
+            .and_then(|path| all.iter().position(|p| *p == path));
+        let (idx, wrapped) = match current {
+            Some(current) => next_match(&matches, current, backward)?,
+            None => (*matches.first()?, false),
+        };
+        self.reveal_entry(&all[idx]);
+        Some(wrapped)
+    }
+
+    /// expands all collapsed entries containing the entry at `path` and
+    /// focuses it
+    fn reveal_entry(&mut self, path: &[usize]) {
+        for len in 3..=path.len() {
+            if let Some(entry) = self.document.entry_mut(&path[..len - 1]) {
+                entry.collapsed = false;
+            }
+        }
+        self.select_entry_path(path);
+    }
+
+    /// removes the entry at `path` and keeps the focus in bounds
+    fn remove_entry(&mut self, path: &[usize]) {
+        self.document.remove_entry(path);
+        self.clamp_focus();
+    }
+
+    /// cycles through the sort modes. sorting only changes the order entries
+    /// are shown in, the document keeps its order
+    pub fn cycle_sort_mode(&mut self) {
+        self.keep_focus(|ui| {
+            ui.current_sort_mode = match ui.current_sort_mode {
+                SortMode::Default => SortMode::ByDeadlineDescending,
+                SortMode::ByDeadlineDescending => SortMode::ByDeadlineAscending,
+                SortMode::ByDeadlineAscending => SortMode::ByPriority,
+                SortMode::ByPriority => SortMode::ByCreated,
+                SortMode::ByCreated => SortMode::ByCompleted,
+                SortMode::ByCompleted => SortMode::ByTextAscending,
+                SortMode::ByTextAscending => SortMode::ByTextDescending,
+                SortMode::ByTextDescending => SortMode::Default,
+            }
+        });
+    }
+
+    pub fn set_filter(&mut self, filter: Filter) {
+        self.keep_focus(|ui| ui.filter = filter);
+    }
+
+    /// cycles through the filters that don't need any input
+    pub fn cycle_filter(&mut self) {
+        self.set_filter(self.filter.next());
+    }
+
+    /// asks for a text to filter the entries by
+    pub fn prompt_filter(&mut self) {
+        self.open_prompt(PromptKind::Filter);
+    }
+
+    /// lists the document's tags and assignees to pick some to filter by.
+    /// the ones currently filtered by are picked already
+    pub fn open_tag_picker(&mut self) {
+        let labels = tag_labels(&self.document);
+        if labels.is_empty() {
+            self.message = Some("No #tags or @assignees in this document".into());
+            return;
+        }
+        let picked = match &self.filter {
+            Filter::Tags(picked) => picked.clone(),
+            _ => vec![],
+        };
+        self.tag_picker = Some(TagPicker {
+            labels,
+            picked,
+            cursor: 0,
+        });
+    }
+
+    pub fn is_picking_tags(&self) -> bool {
+        self.tag_picker.is_some()
+    }
+
+    pub fn move_tag_cursor(&mut self, dir: MoveDirection) {
+        if let Some(picker) = &mut self.tag_picker {
+            picker.cursor = match dir {
+                Up => picker.cursor.saturating_sub(1),
+                Down => (picker.cursor + 1).min(picker.labels.len() - 1),
+            };
+        }
+    }
+
+    /// picks the tag under the cursor, or unpicks it if it was picked
+    pub fn toggle_picked_tag(&mut self) {
+        if let Some(picker) = &mut self.tag_picker {
+            let label = &picker.labels[picker.cursor];
+            match picker.picked.iter().position(|p| p == label) {
+                Some(i) => {
+                    picker.picked.remove(i);
+                }
+                None => picker.picked.push(label.clone()),
+            }
+        }
+    }
+
+    /// filters by the picked tags and closes the picker. picking none shows
+    /// all entries again. if nothing was picked explicitly, the tag under the
+    /// cursor is used
+    pub fn submit_tag_picker(&mut self) {
+        let Some(mut picker) = self.tag_picker.take() else {
+            return;
+        };
+        if picker.picked.is_empty() && !matches!(self.filter, Filter::Tags(_)) {
+            picker.picked.push(picker.labels[picker.cursor].clone());
+        }
+        match picker.picked.is_empty() {
+            true => self.set_filter(Filter::All),
+            false => self.set_filter(Filter::Tags(picker.picked)),
+        }
+    }
+
+    pub fn cancel_tag_picker(&mut self) {
+        self.tag_picker = None;
+    }
+
+    /// shows the focused entry's notes to view and edit them
+    pub fn open_notes_pane(&mut self) {
+        if let Some(path) = self.active_entry_path() {
+            self.notes_pane = Some(NotesPane { path, cursor: 0 });
+        }
+    }
+
+    pub fn is_viewing_notes(&self) -> bool {
+        self.notes_pane.is_some()
+    }
+
+    pub fn close_notes_pane(&mut self) {
+        self.notes_pane = None;
+    }
+
+    /// notes of the entry shown in the notes pane
+    fn pane_notes(&self) -> Vec<String> {
+        self.notes_pane
+            .as_ref()
+            .and_then(|pane| self.document.entry(&pane.path))
+            .map_or(vec![], Entry::notes)
+    }
+
+    pub fn move_notes_cursor(&mut self, dir: MoveDirection) {
+        let len = self.pane_notes().len();
+        if let Some(pane) = &mut self.notes_pane {
+            pane.cursor = match dir {
+                Up => pane.cursor.saturating_sub(1),
+                Down => (pane.cursor + 1).min(len.saturating_sub(1)),
+            };
+        }
+    }
+
+    /// opens the note line under the cursor in the line editor
+    pub fn edit_note(&mut self) {
+        let notes = self.pane_notes();
+        let Some(pane) = &self.notes_pane else {
+            return;
+        };
+        let Some(line) = notes.get(pane.cursor) else {
+            return self.open_new_note(Down);
+        };
+        self.editor.start(line, 0, EditMode::Normal);
+        self.edit_target = Some(EditTarget {
+            path: pane.path.clone(),
+            is_new: false,
+            note: Some(pane.cursor),
+        });
+    }
+
+    /// opens a new note line after (`Down`) or before (`Up`) the cursor in the line editor
+    pub fn open_new_note(&mut self, dir: MoveDirection) {
+        let len = self.pane_notes().len();
+        let Some(pane) = &mut self.notes_pane else {
+            return;
+        };
+        if dir == Down && len > 0 {
+            pane.cursor += 1;
+        }
+        pane.cursor = pane.cursor.min(len);
+        self.editor.start("", 0, EditMode::Insert);
+        self.edit_target = Some(EditTarget {
+            path: pane.path.clone(),
+            is_new: true,
+            note: Some(pane.cursor),
+        });
+    }
+
+    /// removes the note line under the cursor
+    pub fn delete_note(&mut self) {
+        let mut notes = self.pane_notes();
+        let Some(pane) = &mut self.notes_pane else {
+            return;
+        };
+        if pane.cursor >= notes.len() {
+            return;
+        }
+        notes.remove(pane.cursor);
+        let path = pane.path.clone();
+        pane.cursor = pane.cursor.min(notes.len().saturating_sub(1));
+        self.begin_transaction();
+        self.document.set_notes(&path, &notes);
+        self.end_transaction();
+    }
+
+    /// writes the line editor's text back into the note line being edited
+    fn commit_note_edit(&mut self, path: &[usize], idx: usize, is_new: bool) {
+        let mut notes = self.document.entry(path).map_or(vec![], Entry::notes);
+        let text = self.editor.text().trim_end().to_string();
+        match is_new {
+            true if text.is_empty() => {
+                if let Some(pane) = &mut self.notes_pane {
+                    pane.cursor = pane.cursor.min(notes.len().saturating_sub(1));
+                }
+                return;
+            }
+            true => notes.insert(idx.min(notes.len()), text),
+            false if notes.get(idx) == Some(&text) => return,
+            false => match notes.get_mut(idx) {
+                Some(line) => *line = text,
+                None => return,
+            },
+        }
+        self.begin_transaction();
+        self.document.set_notes(path, &notes);
+        self.end_transaction();
+    }
+
+    /// state restored by undo/redo
+    pub fn snapshot(&self) -> Snapshot {
+        Snapshot {
+            document: self.document.clone(),
+            active_entry_idx: self.active_entry_idx,
+        }
+    }
+
+    fn restore(&mut self, snapshot: Snapshot) {
+        self.document = snapshot.document;
+        self.active_entry_idx = snapshot.active_entry_idx;
+        self.clamp_focus();
+        let len = self.pane_notes().len();
+        if let Some(pane) = &mut self.notes_pane {
+            pane.cursor = pane.cursor.min(len.saturating_sub(1));
+        }
+    }
+
+    /// starts recording a change for undo. everything up to the matching
+    /// `end_transaction` is undone in one step
+    pub fn begin_transaction(&mut self) {
+        let snapshot = self.snapshot();
+        self.history.begin(snapshot);
+    }
+
+    pub fn end_transaction(&mut self) {
+        self.history.end(&self.document);
+    }
+
+    pub fn undo(&mut self) {
+        match self.history.undo(self.snapshot()) {
+            Some(snapshot) => self.restore(snapshot),
+            None => self.message = Some("Already at oldest change".into()),
+        }
+    }
+
+    pub fn redo(&mut self) {
+        match self.history.redo(self.snapshot()) {
+            Some(snapshot) => self.restore(snapshot),
+            None => self.message = Some("Already at newest change".into()),
         }
     }
 
@@ -325,18 +1539,126 @@ where
     }
 }
 
+/// the tags and assignees of the document, some of which are picked
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct TagPicker {
+    /// `#tags` and `@assignees`
+    pub labels: Vec<String>,
+    pub picked: Vec<String>,
+    pub cursor: usize,
+}
+
+/// an entry whose notes are shown, with the note line under the cursor
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NotesPane {
+    pub path: EntryPath,
+    pub cursor: usize,
+}
+
+/// a single line text prompt shown in the status line
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Prompt {
+    pub kind: PromptKind,
+    pub input: String,
+}
+
+/// what the input of a `Prompt` is used for
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PromptKind {
+    /// natural language deadline for the entry at the path
+    Deadline(EntryPath),
+    /// text to filter the entries by
+    Filter,
+    Search(SearchPrompt),
+}
+
+impl PromptKind {
+    pub fn label(&self) -> &'static str {
+        match self {
+            PromptKind::Deadline(_) => "deadline: ",
+            PromptKind::Filter => "filter: ",
+            PromptKind::Search(prompt) => match (prompt.regex, prompt.backward) {
+                (false, false) => "/",
+                (false, true) => "?",
+                (true, false) => "regex /",
+                (true, true) => "regex ?",
+            },
+        }
+    }
+}
+
+/// state of an incremental search while its pattern is being typed
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SearchPrompt {
+    pub backward: bool,
+    pub regex: bool,
+    /// the entry focused when the search was opened
+    pub origin: Option<EntryPath>,
+    /// the search before this one, restored on cancel
+    pub previous: Option<Search>,
+}
+
+impl SearchPrompt {
+    pub fn search(&self, pattern: &str) -> Search {
+        Search {
+            pattern: pattern.to_string(),
+            backward: self.backward,
+            regex: self.regex,
+        }
+    }
+}
+
+/// a single line of the entry list
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Row {
+    Section(usize),
+    Entry(EntryPath),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum MoveDirection {
     Down,
     Up,
 }
 pub use MoveDirection::*;
 
-#[derive(Debug, Default)]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
 pub enum SortMode {
     #[default]
     Default,
     ByDeadlineDescending,
     ByDeadlineAscending,
+    /// highest priority first, entries without one last
+    ByPriority,
+    /// most recently created first
+    ByCreated,
+    /// most recently completed first, open entries last
+    ByCompleted,
     ByTextAscending,
     ByTextDescending,
 }
+
+impl SortMode {
+    /// order of two sibling entries. `Equal` keeps the document order.
+    /// entries with the same deadline are ordered by priority
+    pub fn compare(&self, a: &Entry, b: &Entry) -> std::cmp::Ordering {
+        let text = |entry: &Entry| entry.text.to_lowercase();
+        let priority = |entry: &Entry| (entry.priority.is_none(), entry.priority);
+        match self {
+            SortMode::Default => Equal,
+            SortMode::ByDeadlineDescending => b
+                .deadline
+                .cmp(&a.deadline)
+                .then_with(|| priority(a).cmp(&priority(b))),
+            SortMode::ByDeadlineAscending => a
+                .deadline
+                .cmp(&b.deadline)
+                .then_with(|| priority(a).cmp(&priority(b))),
+            SortMode::ByPriority => priority(a).cmp(&priority(b)),
+            SortMode::ByCreated => b.created.cmp(&a.created),
+            SortMode::ByCompleted => b.completed.cmp(&a.completed),
+            SortMode::ByTextAscending => text(a).cmp(&text(b)),
+            SortMode::ByTextDescending => text(b).cmp(&text(a)),
+        }
+    }
+}
@@ -0,0 +1,246 @@
+use crate::*;
+
+use std::cmp::Ordering;
+
+use chrono::{Duration, NaiveDateTime};
+
+/// which entries are shown. filtering only changes the view, the document
+/// keeps all entries in their original order
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub enum Filter {
+    #[default]
+    All,
+    /// entries not completed yet
+    Open,
+    /// open entries whose deadline has passed
+    Overdue,
+    /// open entries due in the current (monday to sunday) week
+    DueThisWeek,
+    /// entries containing the text, ignoring case
+    Text(String),
+    /// entries with any of the `#tags` or `@assignees`
+    Tags(Vec<String>),
+}
+
+impl Filter {
+    pub fn matches(&self, entry: &Entry, now: NaiveDateTime) -> bool {
+        match self {
+            Filter::All => true,
+            Filter::Open => !entry.status.is_closed(),
+            Filter::Overdue => !entry.status.is_closed() && entry.deadline.is_some_and(|d| d < now),
+            Filter::DueThisWeek => {
+                let week = now.date().week(chrono::Weekday::Mon);
+                !entry.status.is_closed()
+                    && entry
+                        .deadline
+                        .is_some_and(|d| (week.first_day()..=week.last_day()).contains(&d.date()))
+            }
+            Filter::Text(text) => entry.text.to_lowercase().contains(&text.to_lowercase()),
+            Filter::Tags(labels) => labels.iter().any(|label| match label.split_at(1) {
+                ("#", tag) => entry.tags.iter().any(|t| t.eq_ignore_ascii_case(tag)),
+                ("@", name) => entry.assignees.iter().any(|a| a.eq_ignore_ascii_case(name)),
+                _ => false,
+            }),
+        }
+    }
+
+    /// the next of the filters cycled through with `f`
+    pub fn next(&self) -> Self {
+        match self {
+            Filter::All => Filter::Open,
+            Filter::Open => Filter::Overdue,
+            Filter::Overdue => Filter::DueThisWeek,
+            Filter::DueThisWeek | Filter::Text(_) | Filter::Tags(_) => Filter::All,
+        }
+    }
+
+    pub fn label(&self) -> String {
+        match self {
+            Filter::All => "all".into(),
+            Filter::Open => "open".into(),
+            Filter::Overdue => "overdue".into(),
+            Filter::DueThisWeek => "due this week".into(),
+            Filter::Text(text) => format!("\"{}\"", text),
+            Filter::Tags(labels) => labels.join(" "),
+        }
+    }
+}
+
+/// the `#tags` and `@assignees` used in `document`, e.g. to pick some to filter
+/// by. tags come before assignees, both sorted ignoring case
+pub fn tag_labels(document: &Document) -> Vec<String> {
+    let mut labels: Vec<String> = vec![];
+    for path in document.entry_paths(false) {
+        let entry = document.entry(&path).unwrap();
+        let tags = entry.tags.iter().map(|t| format!("#{t}"));
+        for label in tags.chain(entry.assignees.iter().map(|a| format!("@{a}"))) {
+            if !labels.iter().any(|l| l.eq_ignore_ascii_case(&label)) {
+                labels.push(label);
+            }
+        }
+    }
+    labels.sort_by_key(|label| (label.starts_with('@'), label.to_lowercase()));
+    labels
+}
+
+/// how pressing the deadline of an open entry is
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Urgency {
+    Overdue,
+    DueToday,
+    /// due within the `due_soon` threshold, but not today
+    DueSoon,
+}
+
+impl Urgency {
+    /// urgency of `entry` at `now`, or `None` if it is done, has no deadline
+    /// or is due after `due_soon` from now
+    pub fn of(entry: &Entry, now: NaiveDateTime, due_soon: Duration) -> Option<Self> {
+        let deadline = entry.deadline.filter(|_| !entry.status.is_closed())?;
+        if deadline < now {
+            Some(Urgency::Overdue)
+        } else if deadline.date() == now.date() {
+            Some(Urgency::DueToday)
+        } else if deadline - now <= due_soon {
+            Some(Urgency::DueSoon)
+        } else {
+            None
+        }
+    }
+}
+
+/// how deadlines are shown in the deadline column
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum DeadlineDisplay {
+    /// `(2024-06-20 16:00)`
+    #[default]
+    Absolute,
+    /// `in 2h`, `tomorrow`, `3d overdue`, ...
+    Relative,
+    /// `(2024-06-20 16:00) in 2h`
+    Both,
+}
+
+/// width of the canonical `(YYYY-mm-dd HH:MM)` deadline format
+pub const ABSOLUTE_DEADLINE_WIDTH: usize = "(YYYY-mm-dd HH:MM)".len();
+
+impl DeadlineDisplay {
+    pub fn next(self) -> Self {
+        match self {
+            DeadlineDisplay::Absolute => DeadlineDisplay::Relative,
+            DeadlineDisplay::Relative => DeadlineDisplay::Both,
+            DeadlineDisplay::Both => DeadlineDisplay::Absolute,
+        }
+    }
+
+    /// the deadline column of `entry`, or an empty string if it has no deadline
+    pub fn format(self, entry: &Entry, now: NaiveDateTime) -> String {
+        let Some(deadline) = entry.deadline else {
+            return String::new();
+        };
+        let absolute = deadline.format("(%Y-%m-%d %H:%M)");
+        let relative = || relative_deadline(deadline, now, entry.status.is_closed());
+        match self {
+            DeadlineDisplay::Absolute => absolute.to_string(),
+            DeadlineDisplay::Relative => relative(),
+            DeadlineDisplay::Both => format!("{} {}", absolute, relative()),
+        }
+    }
+}
+
+/// `deadline` relative to `now`, e.g. `in 2h`, `tomorrow` or `3d overdue`.
+/// past deadlines of completed entries are shown as `3d ago` instead
+pub fn relative_deadline(deadline: NaiveDateTime, now: NaiveDateTime, done: bool) -> String {
+    let delta = deadline - now;
+    if delta.num_minutes() == 0 {
+        "now".into()
+    } else if delta < Duration::zero() {
+        let suffix = if done { "ago" } else { "overdue" };
+        format!("{} {}", duration_label(-delta), suffix)
+    } else if delta >= Duration::days(1) && deadline.date() == now.date() + Duration::days(1) {
+        "tomorrow".into()
+    } else {
+        format!("in {}", duration_label(delta))
+    }
+}
+
+/// `delta` in its largest fitting unit, e.g. `45m`, `2h`, `3d`, `2w`, `5mo`
+fn duration_label(delta: Duration) -> String {
+    let days = delta.num_days();
+    match () {
+        _ if delta < Duration::hours(1) => format!("{}m", delta.num_minutes()),
+        _ if delta < Duration::days(1) => format!("{}h", delta.num_hours()),
+        _ if days < 14 => format!("{}d", days),
+        _ if days < 60 => format!("{}w", days / 7),
+        _ if days < 365 => format!("{}mo", days / 30),
+        _ => format!("{}y", days / 365),
+    }
+}
+
+/// paths of the entries of `document` in display order. siblings are ordered
+/// by `compare`, and an entry is shown if `keep` holds for it or any entry
+/// below it. children of collapsed entries are left out if `skip_collapsed`
+pub fn view_paths(
+    document: &Document,
+    skip_collapsed: bool,
+    compare: impl Fn(&Entry, &Entry) -> Ordering,
+    keep: impl Fn(&[usize], &Entry) -> bool,
+) -> Vec<EntryPath> {
+    struct View<C, K> {
+        skip_collapsed: bool,
+        compare: C,
+        keep: K,
+    }
+
+    impl<C, K> View<C, K>
+    where
+        C: Fn(&Entry, &Entry) -> Ordering,
+        K: Fn(&[usize], &Entry) -> bool,
+    {
+        /// appends the shown entries among `blocks` to `paths`. returns whether
+        /// any entry among them is kept
+        fn collect(
+            &self,
+            blocks: &[Block],
+            path: &mut EntryPath,
+            paths: &mut Vec<EntryPath>,
+        ) -> bool {
+            let mut entries: Vec<(usize, &Entry)> = blocks
+                .iter()
+                .enumerate()
+                .filter_map(|(i, block)| match block {
+                    Block::Entry(entry) => Some((i, entry)),
+                    _ => None,
+                })
+                .collect();
+            entries.sort_by(|(_, a), (_, b)| (self.compare)(a, b));
+
+            let mut any_kept = false;
+            for (i, entry) in entries {
+                path.push(i);
+                let mut below = vec![];
+                let kept_below = self.collect(&entry.children, path, &mut below);
+                if kept_below || (self.keep)(path, entry) {
+                    any_kept = true;
+                    paths.push(path.clone());
+                    if !(self.skip_collapsed && entry.collapsed) {
+                        paths.extend(below);
+                    }
+                }
+                path.pop();
+            }
+            any_kept
+        }
+    }
+
+    let view = View {
+        skip_collapsed,
+        compare,
+        keep,
+    };
+    let mut paths = vec![];
+    for (i, section) in document.sections.iter().enumerate() {
+        view.collect(&section.blocks, &mut vec![i], &mut paths);
+    }
+    paths
+}